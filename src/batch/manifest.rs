@@ -0,0 +1,222 @@
+//! Persisted state of a batch transfer
+//!
+//! A [`BatchManifest`] records the recipients, how they are split into transactions and the
+//! status of every transaction. [`BatchTransfer`](super::BatchTransfer) saves it through a
+//! [`ManifestStore`] before and after every submission, so a batch interrupted by a crash can be
+//! resumed without paying anyone twice.
+use crate::sui_types::base_types::SuiAddress;
+use crate::sui_types::digests::TransactionDigest;
+use crate::types::timestamp::Timestamp;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+/// Payment to one recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recipient {
+    /// Recipient address
+    pub address: SuiAddress,
+    /// Decimal amount, e.g. `1.5`
+    pub amount: String,
+    /// Caller reference, e.g. an employee or claim ID, sent as remark of orders
+    pub reference: Option<String>,
+}
+
+impl Recipient {
+    pub fn new(address: SuiAddress, amount: impl Into<String>) -> Self {
+        Self {
+            address,
+            amount: amount.into(),
+            reference: None,
+        }
+    }
+
+    pub fn reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+}
+
+/// Status of one transaction of a batch, shared by all its recipients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChunkStatus {
+    /// Not submitted yet
+    Pending,
+    /// Built and possibly sent, the outcome is not known yet
+    ///
+    /// Never resubmitted blindly; resolved by
+    /// [`BatchTransfer::reconcile`](super::BatchTransfer::reconcile).
+    Submitted {
+        hash: TransactionDigest,
+        /// Local time the transaction was submitted
+        #[serde(default)]
+        submitted_at: Timestamp,
+        /// Local time of the first lookup that did not find the transaction on chain
+        #[serde(default)]
+        not_found_since: Option<Timestamp>,
+    },
+    /// Executed successfully
+    Succeeded { hash: TransactionDigest },
+    /// Failed, the recipients were not paid
+    Failed {
+        hash: Option<TransactionDigest>,
+        reason: String,
+    },
+}
+
+impl ChunkStatus {
+    /// Transaction hash, once known
+    pub fn hash(&self) -> Option<TransactionDigest> {
+        match self {
+            ChunkStatus::Pending => None,
+            ChunkStatus::Submitted { hash, .. } | ChunkStatus::Succeeded { hash } => Some(*hash),
+            ChunkStatus::Failed { hash, .. } => *hash,
+        }
+    }
+
+    /// Whether the outcome is final
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ChunkStatus::Succeeded { .. } | ChunkStatus::Failed { .. }
+        )
+    }
+}
+
+/// Recipients paid by one transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chunk {
+    /// Indices into [`BatchManifest::recipients`]
+    pub recipients: Vec<usize>,
+    #[serde(flatten)]
+    pub status: ChunkStatus,
+    /// Last error that left the chunk in its current status
+    pub last_error: Option<String>,
+}
+
+/// Recipients, chunks and their statuses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchManifest {
+    /// Caller-chosen identifier, also the storage key
+    pub batch_id: String,
+    /// Paying address
+    pub sender: SuiAddress,
+    /// Coin type paid out
+    pub coin_type: String,
+    pub recipients: Vec<Recipient>,
+    pub chunks: Vec<Chunk>,
+    /// Creation time
+    pub create_time: Timestamp,
+    /// Last update time
+    pub update_time: Timestamp,
+}
+
+impl BatchManifest {
+    /// Status of the transaction paying recipient `index`
+    pub fn recipient_status(&self, index: usize) -> Option<&ChunkStatus> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.recipients.contains(&index))
+            .map(|chunk| &chunk.status)
+    }
+
+    /// Whether every chunk has a final outcome
+    pub fn is_finished(&self) -> bool {
+        self.chunks.iter().all(|chunk| chunk.status.is_final())
+    }
+
+    /// Resets failed chunks to pending so the next run retries them
+    pub fn retry_failed(&mut self) {
+        for chunk in self.chunks.iter_mut() {
+            if matches!(chunk.status, ChunkStatus::Failed { .. }) {
+                chunk.status = ChunkStatus::Pending;
+            }
+        }
+    }
+}
+
+/// Persistent storage of batch manifests
+#[async_trait]
+pub trait ManifestStore: Send + Sync {
+    /// Loads a manifest, `None` if there is none
+    async fn load(&self, batch_id: &str) -> anyhow::Result<Option<BatchManifest>>;
+
+    /// Saves a manifest, replacing the previous version
+    async fn save(&self, manifest: &BatchManifest) -> anyhow::Result<()>;
+}
+
+/// Stores each manifest as a JSON file named after its batch ID
+#[derive(Debug, Clone)]
+pub struct FileManifestStore {
+    dir: PathBuf,
+}
+
+impl FileManifestStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, batch_id: &str) -> anyhow::Result<PathBuf> {
+        anyhow::ensure!(
+            !batch_id.is_empty()
+                && batch_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')),
+            "batch ID {:?} must only contain letters, digits, '-' and '_'",
+            batch_id
+        );
+        Ok(self.dir.join(format!("{}.json", batch_id)))
+    }
+}
+
+#[async_trait]
+impl ManifestStore for FileManifestStore {
+    async fn load(&self, batch_id: &str) -> anyhow::Result<Option<BatchManifest>> {
+        match tokio::fs::read(self.path(batch_id)?).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes to a temporary file first, so a crash never leaves a truncated manifest
+    async fn save(&self, manifest: &BatchManifest) -> anyhow::Result<()> {
+        let path = self.path(&manifest.batch_id)?;
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(manifest)?).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
+
+/// Keeps manifests in memory, for tests and short-lived processes
+#[derive(Debug, Default)]
+pub struct MemoryManifestStore {
+    manifests: Mutex<HashMap<String, BatchManifest>>,
+}
+
+#[async_trait]
+impl ManifestStore for MemoryManifestStore {
+    async fn load(&self, batch_id: &str) -> anyhow::Result<Option<BatchManifest>> {
+        let manifests = self
+            .manifests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(manifests.get(batch_id).cloned())
+    }
+
+    async fn save(&self, manifest: &BatchManifest) -> anyhow::Result<()> {
+        self.manifests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(manifest.batch_id.clone(), manifest.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,1237 @@
+//! Batch transfers to many recipients
+//!
+//! Payroll and airdrops pay hundreds of addresses at once. [`BatchTransfer`] splits the
+//! recipients into transactions, submits them and tracks the outcome of every recipient in a
+//! [`BatchManifest`]:
+//!
+//! * [`SubmitMode::Sponsored`] pays up to [`BatchConfig::max_recipients_per_tx`] recipients per
+//!   programmable transaction, sized to stay under [`BatchConfig::max_tx_bytes`], and submits
+//!   it with [`TransferApi::build_sponsor_tx`] and [`TransferApi::do_proxy_pay_tx`]
+//! * [`SubmitMode::Order`] creates one transfer order per recipient with
+//!   [`TransferApi::create_order`] and [`TransferApi::send_tx`]
+//!
+//! The manifest is saved through a [`ManifestStore`] before every submission and after every
+//! outcome. A transaction whose outcome is unknown, e.g. because the process crashed after
+//! sending it, is never resubmitted blindly: [`BatchTransfer::reconcile`] looks it up first,
+//! sponsored transactions on chain through a [`TxLookup`] and orders with
+//! [`TransferApi::query_order`]. [`BatchTransfer::report`] summarizes the batch for settlement.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::batch::manifest::{FileManifestStore, Recipient};
+//! use onechain_wallet_rust_sdk::batch::{BatchConfig, BatchTransfer};
+//! use onechain_wallet_rust_sdk::rpc::TransferApi;
+//! use onechain_wallet_rust_sdk::sui_types::base_types::SuiAddress;
+//! use onechain_wallet_rust_sdk::utils::chain::JsonRpcChainInfo;
+//! use std::sync::Arc;
+//!
+//! async fn payroll<T: TransferApi>(
+//!     client: &T,
+//!     sender: SuiAddress,
+//!     recipients: Vec<Recipient>,
+//!     sign: impl Fn(&str) -> anyhow::Result<String> + Send + Sync + 'static,
+//! ) -> anyhow::Result<()> {
+//!     let chain = JsonRpcChainInfo::new("https://rpc-devnet.onelabs.cc:443", None)?;
+//!     let batch = BatchTransfer::new(BatchConfig::default(), Arc::new(sign))
+//!         .with_coin_source(Arc::new(chain.clone()))
+//!         .with_tx_lookup(Arc::new(chain))
+//!         .with_store(Arc::new(FileManifestStore::new("batches")));
+//!     let mut manifest = batch.resume_or_plan("payroll-2024-06", sender, recipients).await?;
+//!     batch.run(client, &mut manifest).await?;
+//!     println!("{}", serde_json::to_string_pretty(&batch.report(&manifest)?)?);
+//!     Ok(())
+//! }
+//! ```
+
+pub mod manifest;
+
+use crate::batch::manifest::{BatchManifest, Chunk, ChunkStatus, ManifestStore, Recipient};
+use crate::rpc::TransferApi;
+use crate::sui_types::base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress};
+use crate::sui_types::digests::{ObjectDigest, TransactionDigest};
+use crate::sui_types::transaction::TransactionKind;
+use crate::types::timestamp::Timestamp;
+use crate::types::transfer::{
+    ProxyPayTxReq, TransferOrderQueryReq, TransferOrderReq, TransferOrderTxReq,
+};
+use crate::utils::chain::{JsonRpcChainInfo, RpcError};
+use crate::utils::ptb::{SponsorTxForm, TxBuilder};
+use crate::utils::tx_preview::GAS_COIN_TYPE;
+use crate::utils::{format_units, parse_units};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How the transactions of a batch are submitted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubmitMode {
+    /// Many recipients per sponsored programmable transaction
+    #[default]
+    Sponsored,
+    /// One transfer order per recipient
+    Order,
+}
+
+/// Settings of a [`BatchTransfer`]
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub mode: SubmitMode,
+    /// Coin type paid out, [`GAS_COIN_TYPE`] by default
+    pub coin_type: String,
+    /// Decimals of the coin, used to convert recipient amounts into base units
+    pub decimals: u32,
+    /// Maximum number of recipients per transaction
+    pub max_recipients_per_tx: usize,
+    /// Maximum size of a serialized transaction
+    pub max_tx_bytes: usize,
+    /// Part of [`BatchConfig::max_tx_bytes`] kept free for coin inputs and gas data
+    pub reserved_tx_bytes: usize,
+    /// Gas limit passed to `build_sponsor_tx`, e.g. `0.01`
+    pub gas_budget: Option<String>,
+    /// Time a sponsored transaction must stay unknown to the chain before its chunk is
+    /// resubmitted, see [`BatchTransfer::reconcile`]
+    pub not_found_grace: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            mode: SubmitMode::Sponsored,
+            coin_type: GAS_COIN_TYPE.to_string(),
+            decimals: 9,
+            max_recipients_per_tx: 256,
+            max_tx_bytes: 128 * 1024,
+            reserved_tx_bytes: 16 * 1024,
+            gas_budget: None,
+            not_found_grace: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Signs base64 transaction bytes and returns the base64 `GenericSignature`
+///
+/// Implemented for closures, e.g. wrapping
+/// [`zklogin_sign_tx_bytes`](crate::utils::zk_login::zklogin_sign_tx_bytes) or
+/// [`sign_tx_bytes`](crate::utils::multisig::sign_tx_bytes).
+pub trait TxSigner: Send + Sync {
+    fn sign_tx_bytes(&self, tx_bytes: &str) -> anyhow::Result<String>;
+}
+
+impl<F> TxSigner for F
+where
+    F: Fn(&str) -> anyhow::Result<String> + Send + Sync,
+{
+    fn sign_tx_bytes(&self, tx_bytes: &str) -> anyhow::Result<String> {
+        self(tx_bytes)
+    }
+}
+
+/// Source of the coins paying a sponsored transaction
+#[async_trait]
+pub trait CoinSource: Send + Sync {
+    /// Selects coins of `coin_type` owned by `owner` worth at least `amount` base units
+    async fn select_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: &str,
+        amount: u64,
+    ) -> anyhow::Result<Vec<ObjectRef>>;
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoinPage {
+    data: Vec<CoinEntry>,
+    next_cursor: Option<String>,
+    has_next_page: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CoinEntry {
+    coin_object_id: String,
+    version: String,
+    digest: String,
+    balance: String,
+}
+
+#[async_trait]
+impl CoinSource for JsonRpcChainInfo {
+    async fn select_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: &str,
+        amount: u64,
+    ) -> anyhow::Result<Vec<ObjectRef>> {
+        let mut coins = Vec::new();
+        let mut total: u128 = 0;
+        let mut cursor: Option<String> = None;
+        loop {
+            let page: CoinPage = self
+                .request("suix_getCoins", json!([owner, coin_type, cursor, 50]))
+                .await?;
+            for coin in page.data {
+                coins.push((
+                    ObjectID::from_str(&coin.coin_object_id)?,
+                    SequenceNumber::from_u64(coin.version.parse()?),
+                    ObjectDigest::from_str(&coin.digest)?,
+                ));
+                total += coin.balance.parse::<u128>()?;
+                if total >= amount as u128 {
+                    return Ok(coins);
+                }
+            }
+            if !page.has_next_page || page.next_cursor.is_none() {
+                anyhow::bail!(
+                    "{} owns {} base units of {}, {} are needed",
+                    owner,
+                    total,
+                    coin_type,
+                    amount
+                );
+            }
+            cursor = page.next_cursor;
+        }
+    }
+}
+
+/// Outcome of a transaction looked up on chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxOutcome {
+    /// The node does not know the transaction
+    NotFound,
+    /// Executed successfully
+    Succeeded,
+    /// Executed and aborted, no coins were transferred
+    Failed { reason: String },
+}
+
+/// Source of the on-chain outcome of sponsored transactions
+///
+/// Sponsored transactions are not transfer orders, so their outcome can only be looked up by
+/// digest on chain.
+#[async_trait]
+pub trait TxLookup: Send + Sync {
+    /// Looks up the transaction `digest`
+    ///
+    /// Returns [`TxOutcome::NotFound`] only if the node definitely does not know the
+    /// transaction, and an error if it could not tell.
+    async fn tx_outcome(&self, digest: TransactionDigest) -> anyhow::Result<TxOutcome>;
+}
+
+#[derive(Deserialize)]
+struct TxBlock {
+    effects: Option<TxEffects>,
+}
+
+#[derive(Deserialize)]
+struct TxEffects {
+    status: TxStatus,
+}
+
+#[derive(Deserialize)]
+struct TxStatus {
+    status: String,
+    error: Option<String>,
+}
+
+/// Message of the error returned by `sui_getTransactionBlock` for unknown digests
+const TX_NOT_FOUND: &'static str = "Could not find the referenced transaction";
+
+#[async_trait]
+impl TxLookup for JsonRpcChainInfo {
+    /// Queries `sui_getTransactionBlock`, a transaction executed very recently may not have
+    /// reached the node yet
+    async fn tx_outcome(&self, digest: TransactionDigest) -> anyhow::Result<TxOutcome> {
+        let result = self
+            .request::<TxBlock>(
+                "sui_getTransactionBlock",
+                json!([digest, {"showEffects": true}]),
+            )
+            .await;
+        let block = match result {
+            Ok(block) => block,
+            Err(err)
+                if err
+                    .downcast_ref::<RpcError>()
+                    .is_some_and(|err| err.message.contains(TX_NOT_FOUND)) =>
+            {
+                return Ok(TxOutcome::NotFound);
+            }
+            Err(err) => return Err(err),
+        };
+        let status = block
+            .effects
+            .ok_or_else(|| anyhow::anyhow!("transaction {} returned without effects", digest))?
+            .status;
+        match status.status.as_str() {
+            "success" => Ok(TxOutcome::Succeeded),
+            "failure" => Ok(TxOutcome::Failed {
+                reason: status.error.unwrap_or_else(|| status.status.clone()),
+            }),
+            other => anyhow::bail!("transaction {} has unknown status {}", digest, other),
+        }
+    }
+}
+
+/// Outcome of one recipient in a [`SettlementReport`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettlementLine {
+    pub address: SuiAddress,
+    pub amount: String,
+    pub reference: Option<String>,
+    #[serde(flatten)]
+    pub status: ChunkStatus,
+}
+
+/// Summary of a batch for settlement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettlementReport {
+    pub batch_id: String,
+    pub sender: SuiAddress,
+    pub coin_type: String,
+    /// Number of recipients paid
+    pub succeeded: usize,
+    /// Number of recipients whose payment failed
+    pub failed: usize,
+    /// Number of recipients not submitted yet or with an unknown outcome
+    pub pending: usize,
+    /// Total amount of the batch
+    pub total_amount: String,
+    /// Amount paid successfully
+    pub paid_amount: String,
+    pub lines: Vec<SettlementLine>,
+}
+
+/// Runs batch transfers, see the [module documentation](self)
+pub struct BatchTransfer {
+    config: BatchConfig,
+    signer: Arc<dyn TxSigner>,
+    coins: Option<Arc<dyn CoinSource>>,
+    tx_lookup: Option<Arc<dyn TxLookup>>,
+    store: Option<Arc<dyn ManifestStore>>,
+}
+
+impl BatchTransfer {
+    pub fn new(config: BatchConfig, signer: Arc<dyn TxSigner>) -> Self {
+        Self {
+            config,
+            signer,
+            coins: None,
+            tx_lookup: None,
+            store: None,
+        }
+    }
+
+    /// Sets the coin source, required for [`SubmitMode::Sponsored`]
+    pub fn with_coin_source(mut self, coins: Arc<dyn CoinSource>) -> Self {
+        self.coins = Some(coins);
+        self
+    }
+
+    /// Sets the lookup of sponsored transactions, required to reconcile
+    /// [`SubmitMode::Sponsored`] batches
+    pub fn with_tx_lookup(mut self, tx_lookup: Arc<dyn TxLookup>) -> Self {
+        self.tx_lookup = Some(tx_lookup);
+        self
+    }
+
+    /// Sets the store manifests are persisted to
+    pub fn with_store(mut self, store: Arc<dyn ManifestStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn config(&self) -> &BatchConfig {
+        &self.config
+    }
+
+    /// Splits recipients into transactions
+    ///
+    /// # Error
+    ///
+    /// Returns an error for an empty recipient list or an amount that is not a positive
+    /// decimal with at most [`BatchConfig::decimals`] decimals
+    pub fn plan(
+        &self,
+        batch_id: &str,
+        sender: SuiAddress,
+        recipients: Vec<Recipient>,
+    ) -> anyhow::Result<BatchManifest> {
+        anyhow::ensure!(!recipients.is_empty(), "batch has no recipients");
+        for recipient in recipients.iter() {
+            let units = parse_units(&recipient.amount, self.config.decimals)?;
+            anyhow::ensure!(units > 0, "amount for {} is zero", recipient.address);
+        }
+        let per_tx = match self.config.mode {
+            SubmitMode::Order => 1,
+            SubmitMode::Sponsored => self.recipients_per_tx()?,
+        };
+        let chunks = (0..recipients.len())
+            .collect::<Vec<_>>()
+            .chunks(per_tx)
+            .map(|indices| Chunk {
+                recipients: indices.to_vec(),
+                status: ChunkStatus::Pending,
+                last_error: None,
+            })
+            .collect();
+        let now = Timestamp::now();
+        Ok(BatchManifest {
+            batch_id: batch_id.to_string(),
+            sender,
+            coin_type: self.config.coin_type.clone(),
+            recipients,
+            chunks,
+            create_time: now,
+            update_time: now,
+        })
+    }
+
+    /// Loads the manifest of `batch_id` from the store, or plans and saves a new one
+    ///
+    /// # Error
+    ///
+    /// Returns an error if a stored manifest has different recipients
+    pub async fn resume_or_plan(
+        &self,
+        batch_id: &str,
+        sender: SuiAddress,
+        recipients: Vec<Recipient>,
+    ) -> anyhow::Result<BatchManifest> {
+        let stored = match &self.store {
+            Some(store) => store.load(batch_id).await?,
+            None => None,
+        };
+        if let Some(manifest) = stored {
+            anyhow::ensure!(
+                manifest.sender == sender && manifest.recipients == recipients,
+                "stored batch {} has different recipients",
+                batch_id
+            );
+            return Ok(manifest);
+        }
+        let mut manifest = self.plan(batch_id, sender, recipients)?;
+        self.save(&mut manifest).await?;
+        Ok(manifest)
+    }
+
+    /// Resolves chunks whose outcome is unknown, then submits all pending chunks
+    ///
+    /// Stops at the first chunk whose outcome cannot be determined, since the coins it used
+    /// may still be locked by it; the manifest keeps the chunk as submitted and a later run
+    /// reconciles it.
+    pub async fn run<T: TransferApi>(
+        &self,
+        client: &T,
+        manifest: &mut BatchManifest,
+    ) -> anyhow::Result<()> {
+        self.reconcile(client, manifest).await?;
+        for index in 0..manifest.chunks.len() {
+            match &manifest.chunks[index].status {
+                ChunkStatus::Pending => {}
+                ChunkStatus::Submitted { hash, .. } => {
+                    anyhow::bail!("outcome of transaction {} is still unknown", hash)
+                }
+                _ => continue,
+            }
+            let result = match self.config.mode {
+                SubmitMode::Sponsored => self.submit_sponsored(client, manifest, index).await,
+                SubmitMode::Order => self.submit_order(client, manifest, index).await,
+            };
+            if let Err(err) = result {
+                let chunk = &mut manifest.chunks[index];
+                chunk.last_error = Some(err.to_string());
+                if chunk.status == ChunkStatus::Pending {
+                    // Nothing was sent, so nothing can have been executed
+                    chunk.status = ChunkStatus::Failed {
+                        hash: None,
+                        reason: err.to_string(),
+                    };
+                    self.save(manifest).await?;
+                    continue;
+                }
+                self.save(manifest).await?;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Looks up the outcome of submitted chunks
+    ///
+    /// Sponsored transactions are looked up by digest with the [`TxLookup`], orders with
+    /// [`TransferApi::query_order`], according to [`BatchConfig::mode`]. A sponsored chunk is
+    /// reset to pending only once the lookup definitely did not find its transaction twice, the
+    /// first time at least [`BatchConfig::not_found_grace`] after submission and the second
+    /// time at least as long after the first. Lookup errors leave the chunk submitted.
+    ///
+    /// The expiration returned by `build_sponsor_tx` is not relied upon, since its unit is not
+    /// documented.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if a sponsored chunk is submitted and no [`TxLookup`] is set
+    pub async fn reconcile<T: TransferApi>(
+        &self,
+        client: &T,
+        manifest: &mut BatchManifest,
+    ) -> anyhow::Result<()> {
+        let mut changed = false;
+        for chunk in manifest.chunks.iter_mut() {
+            let ChunkStatus::Submitted {
+                hash,
+                submitted_at,
+                not_found_since,
+            } = chunk.status.clone()
+            else {
+                continue;
+            };
+            let status = match self.config.mode {
+                SubmitMode::Sponsored => {
+                    let tx_lookup = self.tx_lookup.as_ref().ok_or_else(|| {
+                        anyhow::anyhow!("reconciling sponsored batches needs a transaction lookup")
+                    })?;
+                    tx_lookup
+                        .tx_outcome(hash)
+                        .await
+                        .map(|outcome| match outcome {
+                            TxOutcome::Succeeded => Some(ChunkStatus::Succeeded { hash }),
+                            TxOutcome::Failed { reason } => Some(ChunkStatus::Failed {
+                                hash: Some(hash),
+                                reason,
+                            }),
+                            TxOutcome::NotFound => self.not_found_status(
+                                hash,
+                                submitted_at,
+                                not_found_since,
+                                Timestamp::now(),
+                            ),
+                        })
+                }
+                SubmitMode::Order => client
+                    .query_order(TransferOrderQueryReq::new(hash))
+                    .await
+                    .and_then(|resp| resp.into_result())
+                    .map(|order| order_outcome(hash, &order.status)),
+            };
+            match status {
+                Ok(Some(status)) => {
+                    chunk.status = status;
+                    changed = true;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    chunk.last_error = Some(err.to_string());
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.save(manifest).await?;
+        }
+        Ok(())
+    }
+
+    /// Summarizes the outcome of every recipient
+    pub fn report(&self, manifest: &BatchManifest) -> anyhow::Result<SettlementReport> {
+        let decimals = self.config.decimals;
+        let mut report = SettlementReport {
+            batch_id: manifest.batch_id.clone(),
+            sender: manifest.sender,
+            coin_type: manifest.coin_type.clone(),
+            succeeded: 0,
+            failed: 0,
+            pending: 0,
+            total_amount: String::new(),
+            paid_amount: String::new(),
+            lines: Vec::with_capacity(manifest.recipients.len()),
+        };
+        let (mut total, mut paid) = (0u64, 0u64);
+        for (index, recipient) in manifest.recipients.iter().enumerate() {
+            let status = manifest
+                .recipient_status(index)
+                .cloned()
+                .unwrap_or(ChunkStatus::Pending);
+            let units = parse_units(&recipient.amount, decimals)?;
+            total = total.saturating_add(units);
+            match status {
+                ChunkStatus::Succeeded { .. } => {
+                    report.succeeded += 1;
+                    paid = paid.saturating_add(units);
+                }
+                ChunkStatus::Failed { .. } => report.failed += 1,
+                _ => report.pending += 1,
+            }
+            report.lines.push(SettlementLine {
+                address: recipient.address,
+                amount: recipient.amount.clone(),
+                reference: recipient.reference.clone(),
+                status,
+            });
+        }
+        report.total_amount = format_units(total, decimals);
+        report.paid_amount = format_units(paid, decimals);
+        Ok(report)
+    }
+
+    async fn submit_sponsored<T: TransferApi>(
+        &self,
+        client: &T,
+        manifest: &mut BatchManifest,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        let coin_source = self
+            .coins
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("sponsored batches need a coin source"))?;
+        let (addresses, amounts) = self.chunk_payments(manifest, index)?;
+        let total = amounts
+            .iter()
+            .try_fold(0u64, |total, &amount| total.checked_add(amount))
+            .ok_or_else(|| anyhow::anyhow!("total amount of chunk {} overflows u64", index))?;
+        let coins = coin_source
+            .select_coins(manifest.sender, &manifest.coin_type, total)
+            .await?;
+
+        let mut tx = TxBuilder::new();
+        tx.pay(coins, addresses, amounts)?;
+        let form = SponsorTxForm::Kind {
+            gas_budget: self.config.gas_budget.clone(),
+        };
+        let req = tx.sponsor_req(manifest.sender, form)?;
+        let built = client.build_sponsor_tx(req).await?.into_result()?;
+        let req = ProxyPayTxReq {
+            user_sig: self.signer.sign_tx_bytes(&built.raw_transaction)?,
+            tx_bytes: built.raw_transaction,
+            reservation_id: built.reservation_id,
+        };
+        self.mark_submitted(manifest, index, built.hash).await?;
+
+        let resp = client.do_proxy_pay_tx(req).await?.into_result()?;
+        manifest.chunks[index].status = if resp.status {
+            ChunkStatus::Succeeded { hash: resp.hash }
+        } else {
+            ChunkStatus::Failed {
+                hash: Some(resp.hash),
+                reason: "transaction failed".to_string(),
+            }
+        };
+        self.save(manifest).await
+    }
+
+    async fn submit_order<T: TransferApi>(
+        &self,
+        client: &T,
+        manifest: &mut BatchManifest,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        let [recipient] = manifest.chunks[index].recipients[..] else {
+            anyhow::bail!("order chunks must have exactly one recipient");
+        };
+        let recipient = &manifest.recipients[recipient];
+        let req = TransferOrderReq {
+            from_address: manifest.sender,
+            to_address: recipient.address,
+            coin_type: manifest.coin_type.clone(),
+            amount: recipient.amount.clone(),
+            remark: recipient.reference.clone(),
+        };
+        let order = client.create_order(req).await?.into_result()?;
+        let req = TransferOrderTxReq {
+            hash: order.hash,
+            user_sig: self.signer.sign_tx_bytes(&order.raw_transaction)?,
+            tx_bytes: order.raw_transaction,
+        };
+        self.mark_submitted(manifest, index, order.hash).await?;
+
+        let resp = client.send_tx(req).await?.into_result()?;
+        if let Some(status) = order_outcome(resp.hash, &resp.status) {
+            manifest.chunks[index].status = status;
+            self.save(manifest).await?;
+        }
+        Ok(())
+    }
+
+    async fn mark_submitted(
+        &self,
+        manifest: &mut BatchManifest,
+        index: usize,
+        hash: TransactionDigest,
+    ) -> anyhow::Result<()> {
+        manifest.chunks[index].status = ChunkStatus::Submitted {
+            hash,
+            submitted_at: Timestamp::now(),
+            not_found_since: None,
+        };
+        self.save(manifest).await
+    }
+
+    /// Status of a sponsored chunk whose transaction was not found on chain at `now`, `None`
+    /// if it stays unchanged, see [`BatchTransfer::reconcile`]
+    fn not_found_status(
+        &self,
+        hash: TransactionDigest,
+        submitted_at: Timestamp,
+        not_found_since: Option<Timestamp>,
+        now: Timestamp,
+    ) -> Option<ChunkStatus> {
+        let grace = self.config.not_found_grace;
+        match not_found_since {
+            Some(since) if since.saturating_add(grace) <= now => Some(ChunkStatus::Pending),
+            Some(_) => None,
+            None if submitted_at.saturating_add(grace) <= now => Some(ChunkStatus::Submitted {
+                hash,
+                submitted_at,
+                not_found_since: Some(now),
+            }),
+            None => None,
+        }
+    }
+
+    fn chunk_payments(
+        &self,
+        manifest: &BatchManifest,
+        index: usize,
+    ) -> anyhow::Result<(Vec<SuiAddress>, Vec<u64>)> {
+        manifest.chunks[index]
+            .recipients
+            .iter()
+            .map(|&recipient| {
+                let recipient = &manifest.recipients[recipient];
+                Ok((
+                    recipient.address,
+                    parse_units(&recipient.amount, self.config.decimals)?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map(|payments| payments.into_iter().unzip())
+    }
+
+    /// Largest number of recipients whose payment fits the transaction size limit
+    fn recipients_per_tx(&self) -> anyhow::Result<usize> {
+        let size = |count: usize| -> anyhow::Result<usize> {
+            let coin = (
+                ObjectID::ZERO,
+                SequenceNumber::new(),
+                ObjectDigest::new([0; 32]),
+            );
+            let addresses = (0..count)
+                .map(|i| SuiAddress::from_bytes([i as u8 + 1; 32]))
+                .collect::<Result<Vec<_>, _>>()?;
+            let amounts = (0..count).map(|i| u64::MAX - i as u64).collect();
+            let mut tx = TxBuilder::new();
+            tx.pay(vec![coin], addresses, amounts)?;
+            let kind = TransactionKind::ProgrammableTransaction(tx.finish());
+            Ok(bcs::to_bytes(&kind)?.len())
+        };
+        let (one, two) = (size(1)?, size(2)?);
+        let per_recipient = two.saturating_sub(one).max(1);
+        let base = one.saturating_sub(per_recipient);
+        let budget = self
+            .config
+            .max_tx_bytes
+            .saturating_sub(self.config.reserved_tx_bytes)
+            .saturating_sub(base);
+        let per_tx = (budget / per_recipient).min(self.config.max_recipients_per_tx);
+        anyhow::ensure!(
+            per_tx > 0,
+            "transaction size limit too small for one recipient"
+        );
+        Ok(per_tx)
+    }
+
+    async fn save(&self, manifest: &mut BatchManifest) -> anyhow::Result<()> {
+        manifest.update_time = Timestamp::now();
+        match &self.store {
+            Some(store) => store.save(manifest).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Debug for BatchTransfer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchTransfer")
+            .field("config", &self.config)
+            .field("coin_source", &self.coins.is_some())
+            .field("tx_lookup", &self.tx_lookup.is_some())
+            .field("store", &self.store.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Maps an order status to a final chunk status, `None` while the order is in progress
+fn order_outcome(hash: TransactionDigest, status: &str) -> Option<ChunkStatus> {
+    match status {
+        "SUCCESS" => Some(ChunkStatus::Succeeded { hash }),
+        "FAIL" | "CANCEL" | "TIMEOUT" => Some(ChunkStatus::Failed {
+            hash: Some(hash),
+            reason: status.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::batch::manifest::MemoryManifestStore;
+    use crate::testing::{MockClient, TestServer, fail, ok};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    const BUILD_PATH: &'static str = "/transfer/buildSponsorTransaction";
+    const PAY_PATH: &'static str = "/transfer/doProxyPayTx";
+
+    struct Coins;
+
+    #[async_trait]
+    impl CoinSource for Coins {
+        async fn select_coins(
+            &self,
+            _owner: SuiAddress,
+            _coin_type: &str,
+            _amount: u64,
+        ) -> anyhow::Result<Vec<ObjectRef>> {
+            Ok(vec![(
+                ObjectID::random(),
+                SequenceNumber::from_u64(1),
+                ObjectDigest::random(),
+            )])
+        }
+    }
+
+    /// Chain knowing the transactions in `outcomes`, failing every lookup while `down`
+    #[derive(Default)]
+    struct Chain {
+        outcomes: Mutex<HashMap<TransactionDigest, TxOutcome>>,
+        down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl TxLookup for Chain {
+        async fn tx_outcome(&self, digest: TransactionDigest) -> anyhow::Result<TxOutcome> {
+            anyhow::ensure!(!self.down.load(Ordering::SeqCst), "node unavailable");
+            let outcomes = self.outcomes.lock().unwrap();
+            Ok(outcomes
+                .get(&digest)
+                .cloned()
+                .unwrap_or(TxOutcome::NotFound))
+        }
+    }
+
+    /// Sponsor building every transaction as `hash` and executing it successfully
+    fn sponsor(hash: TransactionDigest) -> MockClient {
+        MockClient::new(move |path, _| match path {
+            BUILD_PATH => ok(json!({
+                "hash": hash,
+                "rawTransaction": "dHg=",
+                "expiration": Timestamp::now().saturating_add(MINUTE),
+                "sponsor": SuiAddress::ZERO,
+                "reservationId": "reservation",
+            })),
+            PAY_PATH => ok(json!({"hash": hash, "status": true})),
+            _ => panic!("unexpected call to {}", path),
+        })
+    }
+
+    fn sender() -> SuiAddress {
+        SuiAddress::from_bytes([9; 32]).unwrap()
+    }
+
+    fn recipients(count: u8) -> Vec<Recipient> {
+        (0..count)
+            .map(|i| {
+                Recipient::new(SuiAddress::from_bytes([i; 32]).unwrap(), "1.5")
+                    .reference(format!("emp-{}", i))
+            })
+            .collect()
+    }
+
+    fn batch(config: BatchConfig) -> BatchTransfer {
+        BatchTransfer::new(config, Arc::new(|_: &str| anyhow::Ok(String::new())))
+    }
+
+    /// Sponsored batch paying two recipients per transaction
+    fn sponsored_batch(
+        chain: Arc<Chain>,
+        store: Arc<MemoryManifestStore>,
+        not_found_grace: Duration,
+    ) -> BatchTransfer {
+        let config = BatchConfig {
+            max_recipients_per_tx: 2,
+            not_found_grace,
+            ..Default::default()
+        };
+        BatchTransfer::new(config, Arc::new(|_: &str| anyhow::Ok("c2ln".to_string())))
+            .with_coin_source(Arc::new(Coins))
+            .with_tx_lookup(chain)
+            .with_store(store)
+    }
+
+    /// Plans three recipients in two chunks and stores the first chunk as submitted, as left
+    /// behind by a crash right after sending it
+    async fn crash_after_submit(
+        batch: &BatchTransfer,
+        hash: TransactionDigest,
+        submitted_at: Timestamp,
+    ) {
+        let mut manifest = batch
+            .resume_or_plan("payroll", sender(), recipients(3))
+            .await
+            .unwrap();
+        manifest.chunks[0].status = ChunkStatus::Submitted {
+            hash,
+            submitted_at,
+            not_found_since: None,
+        };
+        batch.save(&mut manifest).await.unwrap();
+    }
+
+    #[test]
+    fn plan_chunks() {
+        let config = BatchConfig {
+            max_recipients_per_tx: 4,
+            ..Default::default()
+        };
+        let manifest = batch(config)
+            .plan("payroll", SuiAddress::ZERO, recipients(10))
+            .unwrap();
+        let sizes = manifest
+            .chunks
+            .iter()
+            .map(|chunk| chunk.recipients.len())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, vec![4, 4, 2]);
+
+        // Every planned chunk fits the size limit with distinct addresses and amounts
+        let config = BatchConfig {
+            max_tx_bytes: 2048,
+            reserved_tx_bytes: 0,
+            ..Default::default()
+        };
+        let airdrop = (0..200u64)
+            .map(|i| {
+                let amount = format!("{}.{:09}", 1000 + i, i * 987_654_321 % 1_000_000_000);
+                Recipient::new(SuiAddress::random_for_testing_only(), amount)
+            })
+            .collect();
+        let batch = batch(config);
+        let manifest = batch.plan("airdrop", SuiAddress::ZERO, airdrop).unwrap();
+        assert!(manifest.chunks.len() > 1);
+        for index in 0..manifest.chunks.len() {
+            let (addresses, amounts) = batch.chunk_payments(&manifest, index).unwrap();
+            let coin = (
+                ObjectID::random(),
+                SequenceNumber::from_u64(1),
+                ObjectDigest::random(),
+            );
+            let mut tx = TxBuilder::new();
+            tx.pay(vec![coin], addresses, amounts).unwrap();
+            let kind = TransactionKind::ProgrammableTransaction(tx.finish());
+            assert!(bcs::to_bytes(&kind).unwrap().len() <= 2048);
+        }
+
+        let config = BatchConfig {
+            mode: SubmitMode::Order,
+            ..Default::default()
+        };
+        let manifest = batch(config)
+            .plan("orders", SuiAddress::ZERO, recipients(3))
+            .unwrap();
+        assert_eq!(manifest.chunks.len(), 3);
+
+        let mut invalid = recipients(1);
+        invalid[0].amount = "0".to_string();
+        assert!(
+            batch(BatchConfig::default())
+                .plan("invalid", SuiAddress::ZERO, invalid)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn settlement_report() {
+        let batch = batch(BatchConfig {
+            max_recipients_per_tx: 2,
+            ..Default::default()
+        });
+        let mut manifest = batch
+            .plan("payroll", SuiAddress::ZERO, recipients(5))
+            .unwrap();
+        let hash = TransactionDigest::random();
+        manifest.chunks[0].status = ChunkStatus::Succeeded { hash };
+        manifest.chunks[1].status = ChunkStatus::Failed {
+            hash: Some(hash),
+            reason: "FAIL".to_string(),
+        };
+
+        let report = batch.report(&manifest).unwrap();
+        assert_eq!((report.succeeded, report.failed, report.pending), (2, 2, 1));
+        assert_eq!(report.total_amount, "7.5");
+        assert_eq!(report.paid_amount, "3");
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["chunks"][0]["status"], "SUCCEEDED");
+        let decoded: BatchManifest = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, manifest);
+
+        manifest.retry_failed();
+        assert_eq!(manifest.chunks[1].status, ChunkStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn resume_does_not_double_pay() {
+        let store = Arc::new(MemoryManifestStore::default());
+        let chain = Arc::new(Chain::default());
+        let batch = sponsored_batch(chain.clone(), store.clone(), MINUTE);
+        let submitted = TransactionDigest::random();
+        crash_after_submit(&batch, submitted, Timestamp::now()).await;
+        chain
+            .outcomes
+            .lock()
+            .unwrap()
+            .insert(submitted, TxOutcome::Succeeded);
+
+        // The restarted process resumes from the store
+        let mut manifest = batch
+            .resume_or_plan("payroll", sender(), recipients(3))
+            .await
+            .unwrap();
+        let resubmitted = TransactionDigest::random();
+        let client = sponsor(resubmitted);
+        batch.run(&client, &mut manifest).await.unwrap();
+        assert_eq!(
+            manifest.chunks[0].status,
+            ChunkStatus::Succeeded { hash: submitted }
+        );
+        assert_eq!(
+            manifest.chunks[1].status,
+            ChunkStatus::Succeeded { hash: resubmitted }
+        );
+        assert_eq!(client.count(BUILD_PATH), 1);
+        assert_eq!(client.count(PAY_PATH), 1);
+        assert!(store.load("payroll").await.unwrap().unwrap().is_finished());
+
+        batch.run(&client, &mut manifest).await.unwrap();
+        assert_eq!(client.count(PAY_PATH), 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_outcome_not_resubmitted() {
+        let store = Arc::new(MemoryManifestStore::default());
+        let chain = Arc::new(Chain::default());
+        let batch = sponsored_batch(chain.clone(), store.clone(), MINUTE);
+        let client = sponsor(TransactionDigest::random());
+        let submitted = TransactionDigest::random();
+
+        // Lookup errors keep the chunk submitted, even long after submission
+        crash_after_submit(&batch, submitted, Timestamp::from_millis(1)).await;
+        chain.down.store(true, Ordering::SeqCst);
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        let err = batch.run(&client, &mut manifest).await.unwrap_err();
+        assert!(err.to_string().contains("still unknown"));
+        assert!(matches!(
+            manifest.chunks[0].status,
+            ChunkStatus::Submitted { .. }
+        ));
+        let last_error = manifest.chunks[0].last_error.as_deref().unwrap();
+        assert!(last_error.contains("node unavailable"));
+
+        // Not found within the grace period, the transaction may still be executed
+        chain.down.store(false, Ordering::SeqCst);
+        crash_after_submit(&batch, submitted, Timestamp::now()).await;
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        assert!(batch.run(&client, &mut manifest).await.is_err());
+        assert!(matches!(
+            manifest.chunks[0].status,
+            ChunkStatus::Submitted {
+                not_found_since: None,
+                ..
+            }
+        ));
+
+        // A single miss after the grace period is recorded but not enough to resubmit
+        crash_after_submit(&batch, submitted, Timestamp::from_millis(1)).await;
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        for _ in 0..2 {
+            assert!(batch.run(&client, &mut manifest).await.is_err());
+            assert!(matches!(
+                manifest.chunks[0].status,
+                ChunkStatus::Submitted {
+                    not_found_since: Some(_),
+                    ..
+                }
+            ));
+        }
+        assert!(client.calls().is_empty());
+
+        // Sponsored chunks cannot be reconciled without a lookup
+        let batch = BatchTransfer::new(
+            BatchConfig::default(),
+            Arc::new(|_: &str| anyhow::Ok("c2ln".to_string())),
+        );
+        let err = batch.reconcile(&client, &mut manifest).await.unwrap_err();
+        assert!(err.to_string().contains("transaction lookup"));
+    }
+
+    #[tokio::test]
+    async fn missing_chunks_resolved() {
+        let store = Arc::new(MemoryManifestStore::default());
+        let chain = Arc::new(Chain::default());
+        let batch = sponsored_batch(chain.clone(), store.clone(), Duration::ZERO);
+
+        // Definitely not found on two lookups, resubmitted by the second run
+        let submitted = TransactionDigest::random();
+        crash_after_submit(&batch, submitted, Timestamp::from_millis(1)).await;
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        let resubmitted = TransactionDigest::random();
+        let client = sponsor(resubmitted);
+        assert!(batch.run(&client, &mut manifest).await.is_err());
+        assert!(client.calls().is_empty());
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        batch.run(&client, &mut manifest).await.unwrap();
+        assert_eq!(client.count(BUILD_PATH), 2);
+        assert_eq!(
+            manifest.chunks[0].status,
+            ChunkStatus::Succeeded { hash: resubmitted }
+        );
+
+        // Failed on chain, final and not resubmitted
+        let store = Arc::new(MemoryManifestStore::default());
+        let batch = sponsored_batch(chain.clone(), store.clone(), Duration::ZERO);
+        crash_after_submit(&batch, submitted, Timestamp::from_millis(1)).await;
+        chain.outcomes.lock().unwrap().insert(
+            submitted,
+            TxOutcome::Failed {
+                reason: "InsufficientCoinBalance".to_string(),
+            },
+        );
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        let client = sponsor(resubmitted);
+        batch.run(&client, &mut manifest).await.unwrap();
+        assert_eq!(client.count(BUILD_PATH), 1);
+        assert_eq!(
+            manifest.chunks[0].status,
+            ChunkStatus::Failed {
+                hash: Some(submitted),
+                reason: "InsufficientCoinBalance".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn seconds_expiration_not_trusted() {
+        let store = Arc::new(MemoryManifestStore::default());
+        let chain = Arc::new(Chain::default());
+        let batch = sponsored_batch(chain, store.clone(), MINUTE);
+        let hash = TransactionDigest::random();
+        // The expiration in seconds reads as a millisecond time long past
+        let client = MockClient::new(move |path, _| match path {
+            BUILD_PATH => ok(json!({
+                "hash": hash,
+                "rawTransaction": "dHg=",
+                "expiration": Timestamp::now().saturating_add(MINUTE).as_millis() / 1000,
+                "sponsor": SuiAddress::ZERO,
+                "reservationId": "reservation",
+            })),
+            PAY_PATH => fail("500", "gateway timeout"),
+            _ => panic!("unexpected call to {}", path),
+        });
+        let mut manifest = batch
+            .resume_or_plan("payroll", sender(), recipients(3))
+            .await
+            .unwrap();
+        assert!(batch.run(&client, &mut manifest).await.is_err());
+        let ChunkStatus::Submitted { submitted_at, .. } = manifest.chunks[0].status else {
+            panic!("expected a submitted chunk");
+        };
+        assert!(submitted_at > Timestamp::now().saturating_sub(MINUTE));
+
+        // Not found right after submission, kept despite the past expiration
+        let mut manifest = store.load("payroll").await.unwrap().unwrap();
+        let err = batch.run(&client, &mut manifest).await.unwrap_err();
+        assert!(err.to_string().contains("still unknown"));
+        assert_eq!(client.count(BUILD_PATH), 1);
+    }
+
+    #[tokio::test]
+    async fn chunk_total_overflow() {
+        let batch = sponsored_batch(
+            Arc::new(Chain::default()),
+            Arc::new(MemoryManifestStore::default()),
+            MINUTE,
+        );
+        let huge = format!("{}", u64::MAX / 1_000_000_000);
+        let recipients = (0..2u8)
+            .map(|i| Recipient::new(SuiAddress::from_bytes([i; 32]).unwrap(), huge.clone()))
+            .collect();
+        let mut manifest = batch.plan("overflow", sender(), recipients).unwrap();
+        let client = sponsor(TransactionDigest::random());
+        batch.run(&client, &mut manifest).await.unwrap();
+        let ChunkStatus::Failed { hash: None, reason } = &manifest.chunks[0].status else {
+            panic!("expected a failed chunk");
+        };
+        assert!(reason.contains("overflows"));
+        assert!(client.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn json_rpc_tx_outcome() {
+        let result =
+            |result: serde_json::Value| json!({"jsonrpc": "2.0", "id": 1, "result": result});
+        let error = |code: i64, message: &str| {
+            let error = json!({"code": code, "message": message});
+            json!({"jsonrpc": "2.0", "id": 1, "error": error})
+        };
+        let server = TestServer::start(vec![
+            (
+                200,
+                result(json!({"effects": {"status": {"status": "success"}}})),
+            ),
+            (
+                200,
+                result(json!({"effects": {"status": {"status": "failure", "error": "MoveAbort"}}})),
+            ),
+            (
+                200,
+                error(
+                    -32602,
+                    "Could not find the referenced transaction [TransactionDigest(abc)].",
+                ),
+            ),
+            (200, error(-32603, "server overloaded")),
+        ])
+        .await;
+        let chain = JsonRpcChainInfo::new(&server.url, None).unwrap();
+        let digest = TransactionDigest::random();
+        assert_eq!(
+            chain.tx_outcome(digest).await.unwrap(),
+            TxOutcome::Succeeded
+        );
+        assert_eq!(
+            chain.tx_outcome(digest).await.unwrap(),
+            TxOutcome::Failed {
+                reason: "MoveAbort".to_string()
+            }
+        );
+        assert_eq!(chain.tx_outcome(digest).await.unwrap(), TxOutcome::NotFound);
+        let err = chain.tx_outcome(digest).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().unwrap().code, -32603);
+
+        let request = server.requests()[0].json();
+        assert_eq!(request["method"], "sui_getTransactionBlock");
+        assert_eq!(request["params"][1]["showEffects"], true);
+    }
+}
@@ -0,0 +1,67 @@
+//! Request and response interceptors
+//!
+//! An [`Interceptor`] registered with
+//! [`OneChainWalletService::add_interceptor`](super::OneChainWalletService::add_interceptor)
+//! sees every request body before it is sent and every response body before it is decoded.
+//! Interceptors run in registration order and may mutate the bodies, e.g. to add fields required
+//! by a gateway or to patch responses while debugging schema changes.
+//!
+//! Bodies carry credentials such as access tokens, JWTs, salts and verification codes. Do not
+//! log them verbatim; log selected fields instead.
+//!
+//! For signed requests [`Interceptor::on_request`] runs on the serialized
+//! [`BaseReq`](crate::types::common::BaseReq) before `merchantSign` is computed, so mutations
+//! are covered by the signature.
+//!
+//! # Example
+//!
+//! ```
+//! use onechain_wallet_rust_sdk::client::interceptor::Interceptor;
+//!
+//! #[derive(Debug)]
+//! struct GatewayTenant(String);
+//!
+//! impl Interceptor for GatewayTenant {
+//!     fn on_request(&self, _path: &str, body: &mut serde_json::Value) -> anyhow::Result<()> {
+//!         if let Some(fields) = body.as_object_mut() {
+//!             fields.insert("tenant".to_string(), self.0.clone().into());
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     fn on_response(&self, path: &str, status: u16, body: &mut serde_json::Value) -> anyhow::Result<()> {
+//!         // log the envelope code only, the data may carry tokens
+//!         tracing::debug!(path, status, code = ?body.get("code"), "response");
+//!         Ok(())
+//!     }
+//! }
+//! ```
+use serde_json::Value;
+use std::fmt::Debug;
+
+/// Hook observing or mutating request and response bodies
+///
+/// Both methods have empty default implementations. Returning an error aborts the call with
+/// that error.
+pub trait Interceptor: Debug + Send + Sync {
+    /// Called with the JSON request body before it is signed and sent
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Endpoint path, e.g. `/did/sendCode`
+    /// * `body` - Request body, `Value::Null` for requests without body
+    fn on_request(&self, _path: &str, _body: &mut Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called with the JSON response envelope before it is decoded
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Endpoint path, e.g. `/did/sendCode`
+    /// * `status` - HTTP status code
+    /// * `body` - Response envelope, including `code`, `msg` and `data`
+    fn on_response(&self, _path: &str, _status: u16, _body: &mut Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
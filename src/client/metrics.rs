@@ -0,0 +1,185 @@
+//! Metrics hook for OneChain Wallet SDK
+//!
+//! This module defines the [`Metrics`] trait which is invoked by
+//! [`OneChainWalletService`](super::OneChainWalletService) for every API call. It allows callers to record:
+//! - Request latency per endpoint path
+//! - HTTP status codes
+//! - Business response codes (`CommonResp.code`)
+//! - Retry attempts performed by the retry middleware, see [`Metrics::record_retries`] for the
+//!   clients this works with
+//! - Time spent generating the RSA merchant signature
+//!
+//! With the `metrics` feature enabled, [`MetricsRecorder`] forwards all measurements to the
+//! [`metrics`](https://docs.rs/metrics) crate facade, so any exporter (e.g. Prometheus) can be used.
+
+use async_trait::async_trait;
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+/// Instrumentation hook for API calls
+///
+/// All methods have empty default implementations, so implementors only need to override
+/// the measurements they are interested in.
+pub trait Metrics: Debug + Send + Sync {
+    /// Records the total latency of a request, including retries
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Endpoint path, e.g. `/did/sendCode`
+    /// * `latency` - Time from sending the request until the response body was read
+    /// * `status` - HTTP status code, `None` if no response was received
+    fn record_request(&self, _path: &str, _latency: Duration, _status: Option<u16>) {}
+
+    /// Records the business code returned in `CommonResp.code`
+    fn record_resp_code(&self, _path: &str, _code: &str, _success: bool) {}
+
+    /// Records the number of retries performed for a request
+    ///
+    /// Retries are only counted when the HTTP client contains [`AttemptCountMiddleware`] after
+    /// the retry middleware, which is the case for the SDK's own client created by
+    /// [`get_rest_client`](super::get_rest_client). With a custom client passed to
+    /// [`OneChainWalletService::new`](super::OneChainWalletService::new) that lacks it, `0` is
+    /// recorded for every request.
+    fn record_retries(&self, _path: &str, _retries: u32) {}
+
+    /// Records the time spent generating the RSA merchant signature
+    fn record_sign(&self, _path: &str, _elapsed: Duration) {}
+}
+
+/// Metrics implementation that discards all measurements
+#[derive(Debug, Clone, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// Attempt counter attached to each request as an extension
+///
+/// [`AttemptCountMiddleware`] increments it once for every attempt, including retries.
+#[derive(Debug, Clone, Default)]
+pub struct AttemptCounter(Arc<AtomicU32>);
+
+impl AttemptCounter {
+    /// Number of attempts performed so far
+    pub fn attempts(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Number of retries performed so far, i.e. attempts after the first one
+    pub fn retries(&self) -> u32 {
+        self.attempts().saturating_sub(1)
+    }
+}
+
+/// Middleware counting the attempts of each request
+///
+/// It must be registered after the retry middleware so that it is executed once per attempt.
+#[derive(Debug, Clone, Default)]
+pub struct AttemptCountMiddleware;
+
+#[async_trait]
+impl Middleware for AttemptCountMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if let Some(counter) = extensions.get::<AttemptCounter>() {
+            counter.0.fetch_add(1, Ordering::Relaxed);
+        }
+        next.run(req, extensions).await
+    }
+}
+
+/// Metrics implementation backed by the `metrics` crate
+///
+/// Recorded metrics:
+/// - `onechain_request_duration_seconds` histogram, labels `path`, `status`
+/// - `onechain_response_code_total` counter, labels `path`, `code`, `success`
+/// - `onechain_request_retries_total` counter, label `path`
+/// - `onechain_sign_duration_seconds` histogram, label `path`
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRecorder;
+
+#[cfg(feature = "metrics")]
+impl Metrics for MetricsRecorder {
+    fn record_request(&self, path: &str, latency: Duration, status: Option<u16>) {
+        let status = status.map_or_else(|| "none".to_string(), |s| s.to_string());
+        metrics::histogram!(
+            "onechain_request_duration_seconds",
+            "path" => path.to_string(),
+            "status" => status
+        )
+        .record(latency.as_secs_f64());
+    }
+
+    fn record_resp_code(&self, path: &str, code: &str, success: bool) {
+        metrics::counter!(
+            "onechain_response_code_total",
+            "path" => path.to_string(),
+            "code" => code.to_string(),
+            "success" => success.to_string()
+        )
+        .increment(1);
+    }
+
+    fn record_retries(&self, path: &str, retries: u32) {
+        metrics::counter!("onechain_request_retries_total", "path" => path.to_string())
+            .increment(retries as u64);
+    }
+
+    fn record_sign(&self, path: &str, elapsed: Duration) {
+        metrics::histogram!("onechain_sign_duration_seconds", "path" => path.to_string())
+            .record(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod test {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn recorder_forwards_to_facade() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let metrics = MetricsRecorder;
+            metrics.record_request("/did/sendCode", Duration::from_millis(20), Some(200));
+            metrics.record_resp_code("/did/sendCode", "000000", true);
+            metrics.record_retries("/did/sendCode", 2);
+            metrics.record_sign("/did/sendCode", Duration::from_millis(1));
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value = |name: &str| {
+            snapshot
+                .iter()
+                .find(|(key, _, _, _)| key.key().name() == name)
+                .map(|(_, _, _, value)| value)
+                .unwrap()
+        };
+        assert!(matches!(
+            value("onechain_request_duration_seconds"),
+            DebugValue::Histogram(values) if values.len() == 1
+        ));
+        assert!(matches!(
+            value("onechain_response_code_total"),
+            DebugValue::Counter(1)
+        ));
+        assert!(matches!(
+            value("onechain_request_retries_total"),
+            DebugValue::Counter(2)
+        ));
+        assert!(matches!(
+            value("onechain_sign_duration_seconds"),
+            DebugValue::Histogram(_)
+        ));
+    }
+}
@@ -2,24 +2,38 @@
 //!
 //! This module provides the main client implementation for OneChain Wallet SDK, including:
 //! - HTTP client configuration and management
-//! - RSA signature functionality
+//! - RSA signature functionality with runtime key rotation
 //! - API call interfaces
+//! - Metrics hooks, see [`metrics`]
+//! - Request and response interceptors, see [`interceptor`]
+//! - Multi-merchant routing over one connection pool, see [`registry`]
 
-use crate::types::common::{BaseReq, CommonResp};
+pub mod interceptor;
+pub mod metrics;
+pub mod registry;
+
+use crate::client::interceptor::Interceptor;
+use crate::client::metrics::{AttemptCountMiddleware, AttemptCounter, Metrics, NoopMetrics};
+use crate::types::common::{BaseReq, CommonResp, MERCHANT_KEY_ID, SIGN_VERSION};
+use crate::types::error::DecodeError;
+use crate::utils::key_ring::{MerchantKeyRing, PlatformKeyRing};
 use crate::{
     rpc::{Call, CallMethod},
-    utils::rsa_sign::{RSASign, RSASignUtils},
+    utils::rsa_sign::{CanonicalMode, RSASign, RSASignUtils},
 };
 use anyhow::Ok;
 use async_trait::async_trait;
+use bytes::Bytes;
+use http::HeaderMap;
 use reqwest::Url;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, RequestBuilder};
 use reqwest_retry::policies::ExponentialBackoff;
 use reqwest_retry::{Jitter, RetryTransientMiddleware};
 use reqwest_tracing::TracingMiddleware;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
-use std::time::Duration;
+use serde_json::Value;
+use std::fmt::{Debug, Formatter};
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, sync::Arc};
 
 /// OneChain Wallet service client
@@ -45,16 +59,26 @@ use std::{collections::HashMap, sync::Arc};
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct OneChainWalletService {
     merchant_id: String,
     /// Base URL of the server
     url: Url,
-    /// RSA signature utility
-    rsa_client: RSASignUtils,
+    /// Merchant signing keys, shared between clones
+    key_ring: Arc<MerchantKeyRing>,
+    /// Platform verification keys, shared between clones
+    platform_keys: Arc<PlatformKeyRing>,
     /// HTTP client
     http_client: Arc<ClientWithMiddleware>,
     global_headers: HashMap<String, String>,
+    /// Metrics hook
+    metrics: Arc<dyn Metrics>,
+    /// Whether requests are validated before being sent
+    validate_requests: bool,
+    /// Whether response data not matching the expected schema is dropped instead of failing
+    lenient_decode: bool,
+    /// Request and response interceptors, in registration order
+    interceptors: Vec<Arc<dyn Interceptor>>,
 }
 
 impl OneChainWalletService {
@@ -75,6 +99,26 @@ impl OneChainWalletService {
         b64der: &str,
         merchant_id: &str,
         client: Option<Arc<ClientWithMiddleware>>,
+    ) -> anyhow::Result<Self> {
+        let key_ring = MerchantKeyRing::new(RSASignUtils::new(b64der)?);
+        Self::with_key_ring(host, Arc::new(key_ring), merchant_id, client)
+    }
+
+    /// Creates a new OneChainWalletService instance from a merchant key ring
+    ///
+    /// The id of the active key is sent in the [`MERCHANT_KEY_ID`] header with every signed request.
+    ///
+    /// # Parameters
+    ///
+    /// * `host` - Base URL of the service
+    /// * `key_ring` - Merchant signing keys
+    /// * `merchant_id` - Merchant ID
+    /// * `client` - Optional HTTP client, if not provided, a new client will be created with default configuration
+    pub fn with_key_ring(
+        host: &str,
+        key_ring: Arc<MerchantKeyRing>,
+        merchant_id: &str,
+        client: Option<Arc<ClientWithMiddleware>>,
     ) -> anyhow::Result<Self> {
         let client = if let Some(client) = client {
             client
@@ -82,19 +126,121 @@ impl OneChainWalletService {
             Arc::new(get_rest_client())
         };
 
-        Ok(Self {
-            merchant_id: merchant_id.to_string(),
-            url: Url::parse(host)?,
-            rsa_client: RSASignUtils::new(b64der)?,
-            http_client: client,
+        Ok(Self::from_parts(
+            Url::parse(host)?,
+            merchant_id.to_string(),
+            key_ring,
+            Arc::new(PlatformKeyRing::new()),
+            client,
+            Arc::new(NoopMetrics),
+        ))
+    }
+
+    pub(crate) fn from_parts(
+        url: Url,
+        merchant_id: String,
+        key_ring: Arc<MerchantKeyRing>,
+        platform_keys: Arc<PlatformKeyRing>,
+        http_client: Arc<ClientWithMiddleware>,
+        metrics: Arc<dyn Metrics>,
+    ) -> Self {
+        Self {
+            merchant_id,
+            url,
+            key_ring,
+            platform_keys,
+            http_client,
             global_headers: HashMap::new(),
-        })
+            metrics,
+            validate_requests: true,
+            lenient_decode: false,
+            interceptors: Vec::new(),
+        }
+    }
+
+    /// Merchant ID used for signed requests
+    pub fn merchant_id(&self) -> &str {
+        &self.merchant_id
     }
 
     pub fn set_header(&mut self, key: String, value: String) {
         self.global_headers.insert(key, value);
     }
 
+    /// Returns the merchant key ring, which allows keys to be swapped at runtime
+    pub fn key_ring(&self) -> &MerchantKeyRing {
+        &self.key_ring
+    }
+
+    /// Replaces the active merchant signing key
+    ///
+    /// Takes effect for all clones of this service without interrupting in-flight requests.
+    /// The new key keeps the canonicalization mode set with
+    /// [`OneChainWalletService::set_canonical_mode`].
+    ///
+    /// # Parameters
+    ///
+    /// * `key_id` - Id of the new key, sent in the [`MERCHANT_KEY_ID`] header
+    /// * `b64der` - Base64 encoded RSA private key
+    pub fn rotate_key(&self, key_id: &str, b64der: &str) -> anyhow::Result<()> {
+        self.key_ring.rotate(key_id, RSASignUtils::new(b64der)?);
+        Ok(())
+    }
+
+    /// Sets the link string canonicalization mode used for `merchantSign`
+    ///
+    /// Non-legacy modes send their version in the [`SIGN_VERSION`] header.
+    pub fn set_canonical_mode(&self, mode: CanonicalMode) {
+        self.key_ring.set_canonical_mode(mode);
+    }
+
+    /// Returns the platform verification keys
+    pub fn platform_keys(&self) -> &PlatformKeyRing {
+        &self.platform_keys
+    }
+
+    /// Verifies a platform signature, e.g. on a callback, with any valid platform key
+    ///
+    /// # Returns
+    ///
+    /// Returns the id of the key that verified the signature
+    pub fn verify<T: Serialize + Debug>(
+        &self,
+        obj: &T,
+        ignore_fields: Vec<&str>,
+        sign: &str,
+        key_id: Option<&str>,
+    ) -> anyhow::Result<String> {
+        self.platform_keys.verify(obj, ignore_fields, sign, key_id)
+    }
+
+    /// Sets the metrics hook invoked for every API call
+    pub fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = metrics;
+    }
+
+    /// Enables or disables client-side request validation, enabled by default
+    ///
+    /// See [`Validate`](crate::types::validate::Validate) for the checks performed.
+    pub fn set_validate_requests(&mut self, enabled: bool) {
+        self.validate_requests = enabled;
+    }
+
+    /// Enables or disables lenient response decoding, disabled by default
+    ///
+    /// When enabled, a response whose `data` does not match the expected type is logged and
+    /// returned without data instead of failing the call, see [`CommonResp::decode`].
+    pub fn set_lenient_decode(&mut self, enabled: bool) {
+        self.lenient_decode = enabled;
+    }
+
+    /// Registers an interceptor that sees every request and response body of this service
+    ///
+    /// Interceptors are shared between clones made after registration.
+    pub fn add_interceptor(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors.push(interceptor);
+    }
+
     fn get_request_builder(
         &self,
         url: Url,
@@ -118,6 +264,107 @@ impl OneChainWalletService {
 
         request
     }
+
+    /// Serializes a request body and runs the request interceptors on it
+    fn request_body<Req: Serialize>(
+        &self,
+        path: &str,
+        req: Option<Req>,
+    ) -> anyhow::Result<Option<Value>> {
+        let mut body = match req {
+            Some(req) => serde_json::to_value(&req)?,
+            None => Value::Null,
+        };
+        self.intercept_request(path, &mut body)?;
+        Ok((!body.is_null()).then_some(body))
+    }
+
+    fn intercept_request(&self, path: &str, body: &mut Value) -> anyhow::Result<()> {
+        for interceptor in self.interceptors.iter() {
+            interceptor.on_request(path, body)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a request and returns the response envelope after the response interceptors ran
+    async fn execute(
+        &self,
+        method: CallMethod,
+        path: &str,
+        header: Option<HashMap<String, String>>,
+        body: Option<Value>,
+    ) -> anyhow::Result<(RawResponse, Value)> {
+        let url = self.url.join(path)?;
+
+        let counter = AttemptCounter::default();
+        let request = self
+            .get_request_builder(url, method, header)
+            .with_extension(counter.clone());
+        let start = Instant::now();
+        // Send request and process response
+        let response = if let Some(body) = body {
+            request.json(&body).send().await
+        } else {
+            request.send().await
+        };
+        self.metrics.record_retries(path, counter.retries());
+        let response =
+            response.inspect_err(|_| self.metrics.record_request(path, start.elapsed(), None))?;
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+
+        // Read and parse response body
+        let body = response.bytes().await;
+        self.metrics
+            .record_request(path, start.elapsed(), Some(status));
+        let raw = RawResponse {
+            status,
+            headers,
+            body: body?,
+        };
+        let mut envelope = serde_json::from_slice::<Value>(&raw.body)
+            .map_err(|error| raw.decode_error(path, error.into()))?;
+        for interceptor in self.interceptors.iter() {
+            interceptor.on_response(path, status, &mut envelope)?;
+        }
+        Ok((raw, envelope))
+    }
+
+    /// Sends a prepared request body and decodes the response
+    async fn send<Resp>(
+        &self,
+        method: CallMethod,
+        path: &str,
+        header: Option<HashMap<String, String>>,
+        body: Option<Value>,
+    ) -> anyhow::Result<CommonResp<Resp>>
+    where
+        Resp: for<'de> Deserialize<'de>,
+    {
+        let (raw, envelope) = self.execute(method, path, header, body).await?;
+        let result = CommonResp::<Resp>::decode_value(envelope, self.lenient_decode)
+            .map_err(|error| raw.decode_error(path, error))?;
+        self.metrics
+            .record_resp_code(path, &result.code, result.success);
+        Ok(result)
+    }
+}
+
+impl Debug for OneChainWalletService {
+    /// Header values such as access tokens are not printed, only the header names
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OneChainWalletService")
+            .field("merchant_id", &self.merchant_id)
+            .field("url", &self.url.as_str())
+            .field("key_ring", &self.key_ring)
+            .field("platform_keys", &self.platform_keys.key_ids())
+            .field("global_headers", &self.global_headers.keys())
+            .field("metrics", &self.metrics)
+            .field("validate_requests", &self.validate_requests)
+            .field("lenient_decode", &self.lenient_decode)
+            .field("interceptors", &self.interceptors)
+            .finish_non_exhaustive()
+    }
 }
 
 impl RSASign for OneChainWalletService {
@@ -136,12 +383,16 @@ impl RSASign for OneChainWalletService {
         obj: &T,
         ignore_fields: Vec<&str>,
     ) -> anyhow::Result<String> {
-        self.rsa_client.sign(obj, ignore_fields)
+        Ok(self.key_ring.sign(obj, ignore_fields)?.sign)
     }
 }
 
 #[async_trait]
 impl Call for OneChainWalletService {
+    fn validate_requests(&self) -> bool {
+        self.validate_requests
+    }
+
     /// Sends API request
     ///
     /// # Parameters
@@ -165,19 +416,8 @@ impl Call for OneChainWalletService {
         Req: Serialize + Debug + Send,
         Resp: for<'de> Deserialize<'de>,
     {
-        let url = self.url.join(&path)?;
-
-        let request = self.get_request_builder(url, method, header);
-        // Send request and process response
-        let response = if let Some(req) = req {
-            request.json(&req).send().await?
-        } else {
-            request.send().await?
-        };
-
-        // Parse response body
-        let result = response.json::<CommonResp<Resp>>().await?;
-        Ok(result)
+        let body = self.request_body(&path, req)?;
+        self.send(method, &path, header, body).await
     }
 
     async fn sign_call<Req, Resp>(
@@ -191,17 +431,78 @@ impl Call for OneChainWalletService {
         Req: Serialize + Debug + Send,
         Resp: for<'de> Deserialize<'de>,
     {
-        let mut base_req = BaseReq::new(self.merchant_id.clone(), req);
-        let merchant_sign = self.sign(&base_req, vec!["merchantSign"])?;
-        base_req.merchant_sign = merchant_sign;
+        let base_req = BaseReq::new(self.merchant_id.clone(), req);
+        let mut body = serde_json::to_value(&base_req)?;
+        self.intercept_request(&path, &mut body)?;
+
+        let start = Instant::now();
+        let merchant_sign = self.key_ring.sign(&body, vec!["merchantSign"])?;
+        self.metrics.record_sign(&path, start.elapsed());
+        if let Value::Object(fields) = &mut body {
+            fields.insert(
+                "merchantSign".to_string(),
+                Value::String(merchant_sign.sign),
+            );
+        }
+
+        let header = if merchant_sign.key_id.is_some() || merchant_sign.version.is_some() {
+            let mut header = header.unwrap_or_default();
+            if let Some(key_id) = merchant_sign.key_id {
+                header.insert(MERCHANT_KEY_ID.to_string(), key_id);
+            }
+            if let Some(version) = merchant_sign.version {
+                header.insert(SIGN_VERSION.to_string(), version.to_string());
+            }
+            Some(header)
+        } else {
+            header
+        };
+        self.send(method, &path, header, Some(body)).await
+    }
+
+    async fn call_raw<Req>(
+        &self,
+        method: CallMethod,
+        path: String,
+        header: Option<HashMap<String, String>>,
+        req: Option<Req>,
+    ) -> anyhow::Result<Value>
+    where
+        Req: Serialize + Debug + Send,
+    {
+        let body = self.request_body(&path, req)?;
+        let (_, envelope) = self.execute(method, &path, header, body).await?;
+        if let Some(code) = envelope.get("code").and_then(Value::as_str) {
+            let success = envelope.get("success").and_then(Value::as_bool);
+            self.metrics
+                .record_resp_code(&path, code, success.unwrap_or(false));
+        }
+        Ok(envelope)
+    }
+}
+
+/// HTTP response kept for error reporting
+struct RawResponse {
+    status: u16,
+    headers: HeaderMap,
+    body: Bytes,
+}
 
-        self.call(method, path, header, Some(base_req)).await
+impl RawResponse {
+    fn decode_error(&self, path: &str, error: anyhow::Error) -> DecodeError {
+        DecodeError {
+            path: path.to_string(),
+            status: self.status,
+            headers: self.headers.clone(),
+            body: String::from_utf8_lossy(&self.body).into_owned(),
+            error,
+        }
     }
 }
 
 /// Creates default HTTP client
 ///
-/// Returns an HTTP client configured with tracing middleware
+/// Returns an HTTP client configured with tracing, retry and attempt counting middleware
 pub fn get_rest_client() -> ClientWithMiddleware {
     let retry_policy = ExponentialBackoff::builder()
         .retry_bounds(Duration::from_secs(1), Duration::from_secs(11))
@@ -211,5 +512,238 @@ pub fn get_rest_client() -> ClientWithMiddleware {
     ClientBuilder::new(reqwest::Client::new())
         .with(TracingMiddleware::default())
         .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+        .with(AttemptCountMiddleware)
         .build()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{PRI_KEY, TestServer, ok, retry_client};
+    use crate::types::common::ACCESS_TOKEN;
+    use crate::utils::rsa_sign::RSAVerifyUtils;
+    use serde_json::json;
+    use std::sync::{Mutex, PoisonError};
+
+    #[derive(Debug, Default)]
+    struct RecordingMetrics {
+        events: Mutex<Vec<String>>,
+        latencies: Mutex<Vec<Duration>>,
+    }
+
+    impl RecordingMetrics {
+        fn push(&self, event: String) {
+            self.events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(event);
+        }
+
+        fn events(&self) -> Vec<String> {
+            self.events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        }
+    }
+
+    impl Metrics for RecordingMetrics {
+        fn record_request(&self, path: &str, latency: Duration, status: Option<u16>) {
+            self.latencies
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(latency);
+            self.push(format!("request {} {:?}", path, status));
+        }
+
+        fn record_resp_code(&self, path: &str, code: &str, success: bool) {
+            self.push(format!("code {} {} {}", path, code, success));
+        }
+
+        fn record_retries(&self, path: &str, retries: u32) {
+            self.push(format!("retries {} {}", path, retries));
+        }
+
+        fn record_sign(&self, path: &str, _elapsed: Duration) {
+            self.push(format!("sign {}", path));
+        }
+    }
+
+    fn service(url: &str, metrics: Arc<RecordingMetrics>) -> OneChainWalletService {
+        let mut service =
+            OneChainWalletService::new(url, PRI_KEY, "1000000", Some(Arc::new(retry_client(2))))
+                .unwrap();
+        service.set_metrics(metrics);
+        service
+    }
+
+    #[tokio::test]
+    async fn records_metrics() {
+        let server = TestServer::start(vec![(503, json!({})), (200, ok(json!("code")))]).await;
+        let metrics = Arc::new(RecordingMetrics::default());
+        let resp: CommonResp<String> = service(&server.url, metrics.clone())
+            .sign_call(
+                CallMethod::Post,
+                "/did/sendCode".to_string(),
+                None,
+                Some(json!({"mobile": "1234567890"})),
+            )
+            .await
+            .unwrap();
+        assert!(resp.is_success());
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(
+            metrics.events(),
+            vec![
+                "sign /did/sendCode",
+                "retries /did/sendCode 1",
+                "request /did/sendCode Some(200)",
+                "code /did/sendCode 000000 true",
+            ]
+        );
+        assert!(metrics.latencies.lock().unwrap()[0] > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn rotate_key_keeps_canonical_mode() {
+        let server = TestServer::start(vec![(200, ok(json!("code")))]).await;
+        let service = service(&server.url, Arc::new(RecordingMetrics::default()));
+        service.set_canonical_mode(CanonicalMode::V1);
+        service.rotate_key("new", PRI_KEY).unwrap();
+        let _: CommonResp<String> = service
+            .sign_call(
+                CallMethod::Post,
+                "/did/sendCode".to_string(),
+                None,
+                Some(json!({"mobile": "1234567890"})),
+            )
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.headers["sign_version"], "1");
+        assert_eq!(request.headers["merchant_key_id"], "new");
+    }
+
+    #[test]
+    fn debug_redacts_credentials() {
+        let mut service =
+            OneChainWalletService::new("https://api.example.com", PRI_KEY, "1000000", None)
+                .unwrap();
+        service.set_header(ACCESS_TOKEN.to_string(), "token-value".to_string());
+        let debug = format!("{:?}", service);
+        assert!(debug.contains("1000000"));
+        assert!(debug.contains(ACCESS_TOKEN));
+        assert!(!debug.contains("token-value"));
+        assert!(!debug.contains(&PRI_KEY[40..80]));
+    }
+
+    #[derive(Debug)]
+    struct TestInterceptor;
+
+    impl Interceptor for TestInterceptor {
+        fn on_request(&self, path: &str, body: &mut Value) -> anyhow::Result<()> {
+            anyhow::ensure!(path != "/rejected", "rejected by interceptor");
+            if let Value::Object(fields) = body {
+                fields.insert("tenant".to_string(), json!("t1"));
+            }
+            Ok(())
+        }
+
+        fn on_response(&self, _path: &str, status: u16, body: &mut Value) -> anyhow::Result<()> {
+            body["data"] = json!(format!("patched {}", status));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors() {
+        let server = TestServer::start(vec![(200, ok(json!("code")))]).await;
+        let mut service = service(&server.url, Arc::new(RecordingMetrics::default()));
+        service.add_interceptor(Arc::new(TestInterceptor));
+
+        let resp: CommonResp<String> = service
+            .sign_call(
+                CallMethod::Post,
+                "/did/sendCode".to_string(),
+                None,
+                Some(json!({"mobile": "1234567890"})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.data.as_deref(), Some("patched 200"));
+
+        // the added field is sent and covered by the signature
+        let mut body = server.requests()[0].json();
+        assert_eq!(body["tenant"], "t1");
+        let sign = body["merchantSign"].as_str().unwrap().to_string();
+        body["merchantSign"] = json!("");
+        RSAVerifyUtils::new(crate::testing::PUB_KEY)
+            .unwrap()
+            .verify(&body, vec!["merchantSign"], &sign)
+            .unwrap();
+
+        // a failing interceptor aborts before anything is sent
+        let result: anyhow::Result<CommonResp<String>> = service
+            .call(
+                CallMethod::Post,
+                "/rejected".to_string(),
+                None,
+                Some(json!({})),
+            )
+            .await;
+        assert!(result.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn call_raw_keeps_envelope() {
+        let mut envelope = ok(json!({"anything": [1, 2]}));
+        envelope["gatewayId"] = json!("g1");
+        let server = TestServer::start(vec![(200, envelope)]).await;
+        let metrics = Arc::new(RecordingMetrics::default());
+        let raw = service(&server.url, metrics.clone())
+            .call_raw(
+                CallMethod::Post,
+                "/custom/endpoint".to_string(),
+                None,
+                Some(json!({"a": 1})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(raw["gatewayId"], "g1");
+        assert_eq!(raw["data"]["anything"][1], 2);
+        assert_eq!(server.requests()[0].json(), json!({"a": 1}));
+        assert_eq!(
+            metrics.events(),
+            vec![
+                "retries /custom/endpoint 0",
+                "request /custom/endpoint Some(200)",
+                "code /custom/endpoint 000000 true",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_failed_requests() {
+        // nothing listens on the discard port
+        let metrics = Arc::new(RecordingMetrics::default());
+        let result: anyhow::Result<CommonResp<String>> =
+            service("http://127.0.0.1:9", metrics.clone())
+                .call(
+                    CallMethod::Get,
+                    "/wallet/queryChainCurrencyForList".to_string(),
+                    None,
+                    None::<()>,
+                )
+                .await;
+        assert!(result.is_err());
+        assert_eq!(
+            metrics.events(),
+            vec![
+                "retries /wallet/queryChainCurrencyForList 2",
+                "request /wallet/queryChainCurrencyForList None",
+            ]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Multi-merchant registry
+//!
+//! Platforms operating several merchant ids can register each merchant's RSA key in a
+//! [`MerchantRegistry`]. All merchants share one [`ClientWithMiddleware`] and therefore one
+//! connection pool, while [`MerchantRegistry::merchant`] yields a shared
+//! [`OneChainWalletService`] that signs requests with that merchant's key.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::client::registry::MerchantRegistry;
+//! use onechain_wallet_rust_sdk::rpc::DIDApi;
+//!
+//! async fn example() -> anyhow::Result<()> {
+//!     let registry = MerchantRegistry::new("https://api.example.com", None)?;
+//!     registry.register("merchant_a", "merchant-a-base64-private-key")?;
+//!     registry.register("merchant_b", "merchant-b-base64-private-key")?;
+//!
+//!     let service = registry.merchant("merchant_b")?;
+//!     let profile = service.get_token_user_profile().await?;
+//!     Ok(())
+//! }
+//! ```
+use super::{OneChainWalletService, get_rest_client};
+use crate::client::metrics::{Metrics, NoopMetrics};
+use crate::utils::key_ring::{MerchantKeyRing, PlatformKeyRing};
+use crate::utils::rsa_sign::RSASignUtils;
+use reqwest::Url;
+use reqwest_middleware::ClientWithMiddleware;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Registry of merchant credentials sharing one HTTP client
+pub struct MerchantRegistry {
+    /// Base URL of the server
+    url: Url,
+    /// HTTP client shared by all merchants
+    http_client: Arc<ClientWithMiddleware>,
+    /// Service per merchant ID, built once at registration and shared by all callers
+    merchants: RwLock<HashMap<String, Arc<OneChainWalletService>>>,
+    /// Platform verification keys, shared by all merchants
+    platform_keys: Arc<PlatformKeyRing>,
+    /// Metrics hook, shared by all merchants
+    metrics: Arc<dyn Metrics>,
+}
+
+impl MerchantRegistry {
+    /// Creates an empty registry
+    ///
+    /// # Parameters
+    ///
+    /// * `host` - Base URL of the service
+    /// * `client` - Optional HTTP client, if not provided, a new client will be created with default configuration
+    pub fn new(host: &str, client: Option<Arc<ClientWithMiddleware>>) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: Url::parse(host)?,
+            http_client: client.unwrap_or_else(|| Arc::new(get_rest_client())),
+            merchants: RwLock::new(HashMap::new()),
+            platform_keys: Arc::new(PlatformKeyRing::new()),
+            metrics: Arc::new(NoopMetrics),
+        })
+    }
+
+    /// Registers a merchant with a Base64 encoded RSA private key, replacing any previous entry
+    pub fn register(&self, merchant_id: &str, b64der: &str) -> anyhow::Result<()> {
+        let key_ring = MerchantKeyRing::new(RSASignUtils::new(b64der)?);
+        self.register_key_ring(merchant_id, Arc::new(key_ring));
+        Ok(())
+    }
+
+    /// Registers a merchant with a key ring, replacing any previous entry
+    pub fn register_key_ring(&self, merchant_id: &str, key_ring: Arc<MerchantKeyRing>) {
+        let service = self.service(merchant_id, key_ring);
+        self.write()
+            .insert(merchant_id.to_string(), Arc::new(service));
+    }
+
+    fn service(&self, merchant_id: &str, key_ring: Arc<MerchantKeyRing>) -> OneChainWalletService {
+        OneChainWalletService::from_parts(
+            self.url.clone(),
+            merchant_id.to_string(),
+            key_ring,
+            self.platform_keys.clone(),
+            self.http_client.clone(),
+            self.metrics.clone(),
+        )
+    }
+
+    /// Removes a merchant, services obtained earlier keep working with the removed key
+    pub fn remove(&self, merchant_id: &str) -> bool {
+        self.write().remove(merchant_id).is_some()
+    }
+
+    /// Registered merchant IDs
+    pub fn merchant_ids(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
+    }
+
+    /// Returns the key ring of a merchant, e.g. to rotate its key
+    pub fn key_ring(&self, merchant_id: &str) -> Option<Arc<MerchantKeyRing>> {
+        self.read()
+            .get(merchant_id)
+            .map(|service| service.key_ring.clone())
+    }
+
+    /// Returns the platform verification keys shared by all merchants
+    pub fn platform_keys(&self) -> &PlatformKeyRing {
+        &self.platform_keys
+    }
+
+    /// Sets the metrics hook of all registered and future merchants
+    ///
+    /// Services obtained earlier keep their previous metrics hook.
+    pub fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = metrics;
+        let key_rings = self
+            .read()
+            .iter()
+            .map(|(merchant_id, service)| (merchant_id.clone(), service.key_ring.clone()))
+            .collect::<Vec<_>>();
+        for (merchant_id, key_ring) in key_rings {
+            self.register_key_ring(&merchant_id, key_ring);
+        }
+    }
+
+    /// Returns the service of a merchant
+    ///
+    /// The service implements [`Call`](crate::rpc::Call) and all API traits. Every call for the
+    /// same merchant returns the same instance, sharing the HTTP client, key ring, platform keys
+    /// and metrics hook with the registry. To set per-caller headers such as access tokens,
+    /// clone the service first: `(*registry.merchant(id)?).clone()`.
+    pub fn merchant(&self, merchant_id: &str) -> anyhow::Result<Arc<OneChainWalletService>> {
+        let Some(service) = self.read().get(merchant_id).cloned() else {
+            anyhow::bail!("merchant {} is not registered", merchant_id);
+        };
+        Ok(service)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Arc<OneChainWalletService>>> {
+        self.merchants
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Arc<OneChainWalletService>>> {
+        self.merchants
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Debug for MerchantRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MerchantRegistry")
+            .field("url", &self.url.as_str())
+            .field("merchants", &self.merchant_ids())
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MerchantRegistry;
+    use crate::client::metrics::Metrics;
+    use crate::rpc::DIDApi;
+    use crate::testing::{PRI_KEY, TestServer, ok, retry_client};
+    use crate::types::did::{Provider, SmsCodeSendReq};
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[derive(Debug, Default)]
+    struct CountingMetrics {
+        requests: AtomicUsize,
+    }
+
+    impl Metrics for CountingMetrics {
+        fn record_request(&self, _path: &str, _latency: Duration, _status: Option<u16>) {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn merchant_is_shared() {
+        let registry = MerchantRegistry::new("http://127.0.0.1:9", None).unwrap();
+        registry.register("a", PRI_KEY).unwrap();
+        registry.register("b", PRI_KEY).unwrap();
+
+        let a = registry.merchant("a").unwrap();
+        assert!(Arc::ptr_eq(&a, &registry.merchant("a").unwrap()));
+        assert!(!Arc::ptr_eq(&a, &registry.merchant("b").unwrap()));
+        assert_eq!(a.merchant_id(), "a");
+        assert!(Arc::ptr_eq(&a.key_ring, &registry.key_ring("a").unwrap()));
+
+        // re-registering replaces the service, earlier ones keep working with the old key
+        registry.register("a", PRI_KEY).unwrap();
+        assert!(!Arc::ptr_eq(&a, &registry.merchant("a").unwrap()));
+
+        assert!(registry.remove("b"));
+        assert!(!registry.remove("b"));
+        assert!(registry.merchant("b").is_err());
+        assert!(registry.key_ring("b").is_none());
+        assert_eq!(registry.merchant_ids(), vec!["a".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn merchant_routing_and_metrics() {
+        let server = TestServer::start(vec![(200, ok(json!("code")))]).await;
+        let mut registry =
+            MerchantRegistry::new(&server.url, Some(Arc::new(retry_client(0)))).unwrap();
+        registry.register("a", PRI_KEY).unwrap();
+        registry.register("b", PRI_KEY).unwrap();
+        let metrics = Arc::new(CountingMetrics::default());
+        registry.set_metrics(metrics.clone());
+
+        for merchant_id in ["b", "a"] {
+            let req = SmsCodeSendReq {
+                mobile: "123123123".to_string(),
+                mobile_prefix: "855".to_string(),
+                provider: Provider::Huione,
+            };
+            let service = registry.merchant(merchant_id).unwrap();
+            service.send_code(req).await.unwrap().get_data().unwrap();
+        }
+
+        let merchant_ids = server
+            .requests()
+            .iter()
+            .map(|request| request.json()["merchantId"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(merchant_ids, vec!["b", "a"]);
+        assert_eq!(metrics.requests.load(Ordering::SeqCst), 2);
+    }
+}
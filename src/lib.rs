@@ -16,8 +16,12 @@
 //!
 //! # Main modules
 //!
+//! * [`batch`] - Pays many recipients in chunked, resumable batch transfers
+//!
 //! * [`client`] - Provides the core client implementation of the SDK
 //!
+//! * [`login`] - Provides login flows built on the identity authentication interfaces
+//!
 //! * [`rpc`] - Contains the definition of all API interfaces
 //!
 //! * [`types`] - Defines the data structure of all requests and responses
@@ -49,7 +53,7 @@
 //! ```no_run
 //! use onechain_wallet_rust_sdk::client::OneChainWalletService;
 //! use onechain_wallet_rust_sdk::rpc::DIDApi;
-//! use onechain_wallet_rust_sdk::types::did::SmsCodeSendReq;
+//! use onechain_wallet_rust_sdk::types::did::{Provider, SmsCodeSendReq};
 //!
 //! async fn send_sms_example() -> anyhow::Result<()> {
 //!     let service = OneChainWalletService::new(
@@ -62,7 +66,7 @@
 //!     let req = SmsCodeSendReq {
 //!         mobile: "12345678".to_string(),
 //!         mobile_prefix: "855".to_string(),
-//!         provider: "huione".to_string(),
+//!         provider: Provider::Huione,
 //!     };
 //!
 //!     let resp = service.send_code(req).await?;
@@ -71,11 +75,16 @@
 //! }
 //! ```
 //!
+pub mod batch;
 pub mod client;
+pub mod login;
 pub mod rpc;
 pub mod types;
 pub mod utils;
 
+#[cfg(test)]
+mod testing;
+
 // Re-export dependencies
 pub use fastcrypto;
 pub use fastcrypto_zkp;
@@ -0,0 +1,111 @@
+//! Pluggable login methods
+//!
+//! Every login ends in the same pipeline: an authorization code is exchanged for a JWT with
+//! [`DIDApi::get_token`], after which [`DIDApi::get_zk_proofs`] and
+//! [`zklogin_material`](crate::utils::zk_login::zklogin_material) derive the zkLogin address.
+//! A [`LoginMethod`] only has to supply the authorization code together with its
+//! [`Provider`] and [`LoginType`]; [`authorize`] runs the exchange.
+//!
+//! * SMS logins obtain the code from [`SmsLoginFlow::verify`](super::sms::SmsLoginFlow::verify)
+//! * OAuth-style logins (Google, Apple, Telegram, ...) receive it on the redirect URL, see
+//!   [`AuthorizationCode::oauth`]. The wire values of these providers and of
+//!   [`LoginType::Oauth`] are not confirmed by the platform yet, see [`Provider`]
+//! * Other flows implement [`LoginMethod`] directly
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::login::method::{AuthorizationCode, authorize};
+//! use onechain_wallet_rust_sdk::rpc::DIDApi;
+//! use onechain_wallet_rust_sdk::types::did::Provider;
+//!
+//! async fn example<T: DIDApi>(client: &T, code_from_redirect: String, nonce: String) -> anyhow::Result<()> {
+//!     let method = AuthorizationCode::oauth(Provider::Google, code_from_redirect);
+//!     let token = authorize(client, &method, nonce).await?;
+//!     // continue with `get_zk_proofs` using `token.jwt_token` and `token.salt`
+//!     Ok(())
+//! }
+//! ```
+use crate::rpc::DIDApi;
+use crate::types::did::{AuthorizeTokenProfileReq, AuthorizeTokenProfileResp, LoginType, Provider};
+use async_trait::async_trait;
+
+/// Source of an authorization code accepted by [`DIDApi::get_token`]
+#[async_trait]
+pub trait LoginMethod: Send + Sync {
+    /// Provider the code was issued by
+    fn provider(&self) -> Provider;
+
+    /// Login type of the code
+    fn login_type(&self) -> LoginType;
+
+    /// Obtains the authorization code
+    async fn authorization_code(&self) -> anyhow::Result<String>;
+}
+
+/// Authorization code that has already been obtained
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizationCode {
+    pub provider: Provider,
+    pub login_type: LoginType,
+    pub code: String,
+}
+
+impl AuthorizationCode {
+    pub fn new(provider: Provider, login_type: LoginType, code: impl Into<String>) -> Self {
+        Self {
+            provider,
+            login_type,
+            code: code.into(),
+        }
+    }
+
+    /// Authentication number returned by [`DIDApi::sms`] for the default provider
+    pub fn sms(code: impl Into<String>) -> Self {
+        Self::new(Provider::default(), LoginType::Sms, code)
+    }
+
+    /// Code received on the redirect URL of an OAuth authorization-code login
+    ///
+    /// Uses [`LoginType::Oauth`], whose wire value is unverified; build the code with
+    /// [`AuthorizationCode::new`] and [`LoginType::Custom`] if the server expects another one.
+    pub fn oauth(provider: Provider, code: impl Into<String>) -> Self {
+        Self::new(provider, LoginType::Oauth, code)
+    }
+}
+
+#[async_trait]
+impl LoginMethod for AuthorizationCode {
+    fn provider(&self) -> Provider {
+        self.provider.clone()
+    }
+
+    fn login_type(&self) -> LoginType {
+        self.login_type.clone()
+    }
+
+    async fn authorization_code(&self) -> anyhow::Result<String> {
+        Ok(self.code.clone())
+    }
+}
+
+/// Exchanges the authorization code of a login method for a JWT
+///
+/// # Parameters
+///
+/// * `client` - API client
+/// * `method` - Login method supplying the authorization code
+/// * `nonce` - zkLogin nonce committing to the ephemeral public key and max epoch
+pub async fn authorize<T: DIDApi, M: LoginMethod + ?Sized>(
+    client: &T,
+    method: &M,
+    nonce: String,
+) -> anyhow::Result<AuthorizeTokenProfileResp> {
+    let req = AuthorizeTokenProfileReq {
+        code: method.authorization_code().await?,
+        nonce,
+        provider: method.provider(),
+        login_type: method.login_type(),
+    };
+    client.get_token(req).await?.into_result()
+}
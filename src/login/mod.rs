@@ -0,0 +1,9 @@
+//! Login helpers for OneChain Wallet SDK
+//!
+//! This module builds on the [`DIDApi`](crate::rpc::DIDApi) calls to implement complete login
+//! flows:
+//! - SMS verification with resend cooldown and attempt tracking, see [`sms`]
+//! - Pluggable login methods feeding the token and zkLogin pipeline, see [`method`]
+
+pub mod method;
+pub mod sms;
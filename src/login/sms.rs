@@ -0,0 +1,505 @@
+//! SMS login flow
+//!
+//! [`SmsLoginFlow`] wraps [`DIDApi::send_code`] and [`DIDApi::sms`] with the bookkeeping every
+//! application otherwise implements itself:
+//!
+//! * Mobile numbers and prefixes are normalized, so `+855 012-345-678` and `855`/`12345678`
+//!   refer to the same session. The normalized form is also what is sent to the server, so
+//!   a national trunk `0` is stripped for calling codes that use one: `+855` `012345678` is
+//!   sent as `12345678`, while `+39` `0612345678` is sent unchanged
+//! * The `code` returned by `send_code` is remembered per mobile number
+//! * Codes cannot be resent before the cooldown has elapsed, even by concurrent callers
+//! * Verification fails locally once the code has expired or too many attempts were made
+//!
+//! Failures detected locally are reported as [`SmsLoginError`], which can be recovered from the
+//! returned `anyhow::Error` with `downcast_ref`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::login::sms::SmsLoginFlow;
+//! use onechain_wallet_rust_sdk::rpc::DIDApi;
+//!
+//! async fn example<T: DIDApi>(client: &T) -> anyhow::Result<()> {
+//!     let flow = SmsLoginFlow::default();
+//!     flow.send_code(client, "+855", "012 345 678").await?;
+//!     // ... the user enters the code received by SMS
+//!     let auth_code = flow.verify(client, "+855", "012 345 678", "000000").await?;
+//!     // `auth_code` is the `code` of `AuthorizeTokenProfileReq`
+//!     Ok(())
+//! }
+//! ```
+use crate::rpc::DIDApi;
+use crate::types::did::{Provider, SmsAuthenticateReq, SmsCodeSendReq};
+use crate::types::validate::{validate_mobile, validate_mobile_prefix};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Limits applied by [`SmsLoginFlow`]
+#[derive(Debug, Clone)]
+pub struct SmsLoginConfig {
+    /// Minimum time between two codes sent to the same mobile number
+    pub resend_cooldown: Duration,
+    /// Time after which a sent code is no longer accepted
+    pub code_ttl: Duration,
+    /// Maximum number of verification attempts per sent code
+    pub max_attempts: u32,
+    /// Access channel sent with every request
+    pub provider: Provider,
+}
+
+impl Default for SmsLoginConfig {
+    fn default() -> Self {
+        Self {
+            resend_cooldown: Duration::from_secs(60),
+            code_ttl: Duration::from_secs(5 * 60),
+            max_attempts: 5,
+            provider: Provider::default(),
+        }
+    }
+}
+
+/// Errors detected locally by [`SmsLoginFlow`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmsLoginError {
+    /// A code was sent recently, retry after `remaining`
+    Cooldown { remaining: Duration },
+    /// No code was sent to this mobile number
+    NotSent,
+    /// The sent code expired, a new one must be sent
+    Expired,
+    /// The maximum number of attempts was reached, a new code must be sent
+    TooManyAttempts,
+}
+
+impl Display for SmsLoginError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmsLoginError::Cooldown { remaining } => {
+                write!(
+                    f,
+                    "sms code resend available in {}s",
+                    remaining.as_secs() + 1
+                )
+            }
+            SmsLoginError::NotSent => f.write_str("no sms code was sent to this mobile number"),
+            SmsLoginError::Expired => f.write_str("sms code expired"),
+            SmsLoginError::TooManyAttempts => f.write_str("too many sms code attempts"),
+        }
+    }
+}
+
+impl std::error::Error for SmsLoginError {}
+
+#[derive(Debug)]
+struct SmsSession {
+    /// Code returned by `send_code`, `None` while the request is in flight
+    code: Option<String>,
+    sent_at: Instant,
+    attempts: u32,
+    /// Identifies the `send_code` call that created the session
+    send_id: u64,
+}
+
+/// Stateful SMS login helper, see the [module documentation](self)
+///
+/// One flow can serve any number of mobile numbers concurrently.
+#[derive(Debug, Default)]
+pub struct SmsLoginFlow {
+    config: SmsLoginConfig,
+    sessions: Mutex<HashMap<(String, String), SmsSession>>,
+    next_send_id: AtomicU64,
+}
+
+impl SmsLoginFlow {
+    pub fn new(config: SmsLoginConfig) -> Self {
+        Self {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+            next_send_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &SmsLoginConfig {
+        &self.config
+    }
+
+    /// Sends a verification code, unless the resend cooldown has not elapsed
+    ///
+    /// The number is sent in its [normalized](normalize_mobile) form. The cooldown starts when
+    /// the request is made, so concurrent calls for the same number send a single code. If the
+    /// request fails, the previously sent code, if any, stays valid.
+    pub async fn send_code<T: DIDApi>(
+        &self,
+        client: &T,
+        mobile_prefix: &str,
+        mobile: &str,
+    ) -> anyhow::Result<()> {
+        let key = normalize_mobile(mobile_prefix, mobile)?;
+        let send_id = self.next_send_id.fetch_add(1, Ordering::Relaxed);
+        // Reserve the session under the lock before the call, so concurrent callers see the cooldown
+        let previous = {
+            let mut sessions = self.lock();
+            if let Some(remaining) = sessions
+                .get(&key)
+                .and_then(|session| self.cooldown_left(session))
+            {
+                return Err(SmsLoginError::Cooldown { remaining }.into());
+            }
+            let reservation = SmsSession {
+                code: None,
+                sent_at: Instant::now(),
+                attempts: 0,
+                send_id,
+            };
+            sessions.insert(key.clone(), reservation)
+        };
+        let req = SmsCodeSendReq {
+            mobile: key.1.clone(),
+            mobile_prefix: key.0.clone(),
+            provider: self.config.provider.clone(),
+        };
+        let result = match client.send_code(req).await {
+            Ok(resp) => resp.into_result(),
+            Err(error) => Err(error),
+        };
+        let mut sessions = self.lock();
+        // The session may have been reset or replaced in the meantime
+        if sessions
+            .get(&key)
+            .is_none_or(|session| session.send_id != send_id)
+        {
+            return result.map(|_| ());
+        }
+        match result {
+            Ok(code) => {
+                if let Some(session) = sessions.get_mut(&key) {
+                    session.code = Some(code);
+                }
+                Ok(())
+            }
+            Err(error) => {
+                match previous {
+                    Some(previous) => sessions.insert(key, previous),
+                    None => sessions.remove(&key),
+                };
+                Err(error)
+            }
+        }
+    }
+
+    /// Verifies the code entered by the user
+    ///
+    /// # Return value
+    ///
+    /// Returns the authentication number to pass as `code` in
+    /// [`AuthorizeTokenProfileReq`](crate::types::did::AuthorizeTokenProfileReq)
+    pub async fn verify<T: DIDApi>(
+        &self,
+        client: &T,
+        mobile_prefix: &str,
+        mobile: &str,
+        sms_code: &str,
+    ) -> anyhow::Result<String> {
+        let key = normalize_mobile(mobile_prefix, mobile)?;
+        // Count the attempt before the call so concurrent attempts cannot exceed the limit
+        let code = {
+            let mut sessions = self.lock();
+            let Some((session, code)) = sessions
+                .get_mut(&key)
+                .and_then(|session| session.code.clone().map(|code| (session, code)))
+            else {
+                return Err(SmsLoginError::NotSent.into());
+            };
+            if session.sent_at.elapsed() > self.config.code_ttl {
+                return Err(SmsLoginError::Expired.into());
+            }
+            if session.attempts >= self.config.max_attempts {
+                return Err(SmsLoginError::TooManyAttempts.into());
+            }
+            session.attempts += 1;
+            code
+        };
+        let req = SmsAuthenticateReq {
+            mobile_prefix: key.0.clone(),
+            mobile: key.1.clone(),
+            code,
+            sms_code: sms_code.trim().into(),
+            provider: self.config.provider.clone(),
+        };
+        let resp = client.sms(req).await?.into_result()?;
+        self.lock().remove(&key);
+        Ok(resp.code)
+    }
+
+    /// Time until a new code can be sent, `None` if it can be sent now
+    pub fn remaining_cooldown(&self, mobile_prefix: &str, mobile: &str) -> Option<Duration> {
+        let key = normalize_mobile(mobile_prefix, mobile).ok()?;
+        let sessions = self.lock();
+        self.cooldown_left(sessions.get(&key)?)
+    }
+
+    fn cooldown_left(&self, session: &SmsSession) -> Option<Duration> {
+        self.config
+            .resend_cooldown
+            .checked_sub(session.sent_at.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Number of verification attempts left for the current code, `None` if no code is pending
+    pub fn attempts_left(&self, mobile_prefix: &str, mobile: &str) -> Option<u32> {
+        let key = normalize_mobile(mobile_prefix, mobile).ok()?;
+        let sessions = self.lock();
+        let session = sessions
+            .get(&key)
+            .filter(|session| session.code.is_some())?;
+        Some(self.config.max_attempts.saturating_sub(session.attempts))
+    }
+
+    /// Forgets the pending code of a mobile number
+    pub fn reset(&self, mobile_prefix: &str, mobile: &str) {
+        if let Ok(key) = normalize_mobile(mobile_prefix, mobile) {
+            self.lock().remove(&key);
+        }
+    }
+
+    /// Forgets all sessions whose code expired
+    pub fn prune(&self) {
+        let ttl = self.config.code_ttl.max(self.config.resend_cooldown);
+        self.lock()
+            .retain(|_, session| session.sent_at.elapsed() <= ttl);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), SmsSession>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Calling codes whose national numbers start with a trunk `0` that is dropped when dialling
+/// in international format
+///
+/// Calling codes not listed here, e.g. Italy's `39` where the leading `0` is part of the
+/// number, are sent as entered.
+pub const TRUNK_PREFIX_CALLING_CODES: &'static [&'static str] = &[
+    "33", "44", "49", "60", "61", "62", "63", "64", "66", "81", "82", "84", "86", "91", "855",
+];
+
+/// Normalizes a mobile prefix and number
+///
+/// Separators (spaces, `-`, `.`, parentheses) are removed, the prefix loses its `+` or `00`
+/// and a number given in international format with the same prefix is reduced to the national
+/// number. For a prefix in [`TRUNK_PREFIX_CALLING_CODES`] a single leading trunk `0` is
+/// dropped, other numbers keep their leading zeros.
+///
+/// # Return value
+///
+/// Returns the prefix and the national number, or a
+/// [`ValidationError`](crate::types::validate::ValidationError) if either is malformed
+pub fn normalize_mobile(mobile_prefix: &str, mobile: &str) -> anyhow::Result<(String, String)> {
+    let strip = |s: &str| {
+        s.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+            .collect::<String>()
+    };
+    let prefix = strip(mobile_prefix);
+    let prefix = prefix
+        .strip_prefix('+')
+        .or_else(|| prefix.strip_prefix("00"))
+        .unwrap_or(prefix.as_str())
+        .to_string();
+    let mobile = strip(mobile);
+    let mobile = mobile
+        .strip_prefix('+')
+        .or_else(|| mobile.strip_prefix("00"))
+        .and_then(|international| international.strip_prefix(prefix.as_str()))
+        .unwrap_or(mobile.as_str());
+    let mobile = match mobile.strip_prefix('0') {
+        Some(national) if TRUNK_PREFIX_CALLING_CODES.contains(&prefix.as_str()) => national,
+        _ => mobile,
+    }
+    .to_string();
+    validate_mobile_prefix("mobilePrefix", &prefix)?;
+    validate_mobile("mobile", &mobile)?;
+    Ok((prefix, mobile))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{MockClient, fail, ok};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const SEND: &'static str = "/did/sendCode";
+    const AUTH: &'static str = "/did/authenticateSms";
+
+    /// Server accepting the sms code `123456` for the auth number `auth-<n>` of the n-th send
+    fn server() -> MockClient {
+        let sent = AtomicU64::new(0);
+        MockClient::new(move |path, body| match path {
+            SEND => ok(json!(format!(
+                "auth-{}",
+                sent.fetch_add(1, Ordering::SeqCst)
+            ))),
+            AUTH if body["smsCode"] == "123456" => ok(json!({"code": body["code"]})),
+            _ => fail("200001", "invalid sms code"),
+        })
+    }
+
+    fn flow(resend_cooldown: Duration, code_ttl: Duration) -> SmsLoginFlow {
+        SmsLoginFlow::new(SmsLoginConfig {
+            resend_cooldown,
+            code_ttl,
+            max_attempts: 2,
+            provider: Provider::Huione,
+        })
+    }
+
+    fn login_error(error: anyhow::Error) -> SmsLoginError {
+        error.downcast::<SmsLoginError>().unwrap()
+    }
+
+    #[tokio::test]
+    async fn send_and_verify() {
+        let client = server();
+        let flow = flow(Duration::from_secs(60), Duration::from_secs(60));
+        flow.send_code(&client, "+855", "012 345 678")
+            .await
+            .unwrap();
+        let auth = flow
+            .verify(&client, "855", "12345678", "123456")
+            .await
+            .unwrap();
+        assert_eq!(auth, "auth-0");
+
+        // the normalized number, without trunk zero, is sent to the server
+        let calls = client.calls();
+        assert_eq!(calls[0].1["mobile"], "12345678");
+        assert_eq!(calls[0].1["mobilePrefix"], "855");
+        assert_eq!(calls[1].1["code"], "auth-0");
+
+        // a verified code cannot be reused
+        let error = flow
+            .verify(&client, "855", "12345678", "123456")
+            .await
+            .unwrap_err();
+        assert_eq!(login_error(error), SmsLoginError::NotSent);
+        assert_eq!(client.count(AUTH), 1);
+    }
+
+    #[tokio::test]
+    async fn cooldown() {
+        let client = server();
+        let flow = flow(Duration::from_secs(60), Duration::from_secs(60));
+        flow.send_code(&client, "855", "12345678").await.unwrap();
+        let error = flow
+            .send_code(&client, "855", "012345678")
+            .await
+            .unwrap_err();
+        assert!(matches!(login_error(error), SmsLoginError::Cooldown { .. }));
+        assert!(flow.remaining_cooldown("855", "12345678").is_some());
+        assert_eq!(client.count(SEND), 1);
+
+        flow.reset("855", "12345678");
+        flow.send_code(&client, "855", "12345678").await.unwrap();
+        assert_eq!(client.count(SEND), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_sends() {
+        let client = Arc::new(server().with_delay(Duration::from_millis(50)));
+        let flow = Arc::new(flow(Duration::from_secs(60), Duration::from_secs(60)));
+        let tasks = (0..4)
+            .map(|_| {
+                let (client, flow) = (client.clone(), flow.clone());
+                tokio::spawn(
+                    async move { flow.send_code(client.as_ref(), "855", "12345678").await },
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut sent = 0;
+        for task in tasks {
+            if task.await.unwrap().is_ok() {
+                sent += 1;
+            }
+        }
+        assert_eq!(sent, 1);
+        assert_eq!(client.count(SEND), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_send_releases_reservation() {
+        let client = MockClient::new(|_, _| fail("500000", "unavailable"));
+        let flow = flow(Duration::from_secs(60), Duration::from_secs(60));
+        assert!(flow.send_code(&client, "855", "12345678").await.is_err());
+        assert_eq!(flow.remaining_cooldown("855", "12345678"), None);
+        assert!(flow.send_code(&client, "855", "12345678").await.is_err());
+        assert_eq!(client.count(SEND), 2);
+    }
+
+    #[tokio::test]
+    async fn expiry() {
+        let client = server();
+        let flow = flow(Duration::ZERO, Duration::from_millis(10));
+        flow.send_code(&client, "855", "12345678").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let error = flow
+            .verify(&client, "855", "12345678", "123456")
+            .await
+            .unwrap_err();
+        assert_eq!(login_error(error), SmsLoginError::Expired);
+        assert_eq!(client.count(AUTH), 0);
+
+        // a new code can be sent and verified
+        flow.send_code(&client, "855", "12345678").await.unwrap();
+        let auth = flow
+            .verify(&client, "855", "12345678", "123456")
+            .await
+            .unwrap();
+        assert_eq!(auth, "auth-1");
+    }
+
+    #[tokio::test]
+    async fn max_attempts() {
+        let client = server();
+        let flow = flow(Duration::from_secs(60), Duration::from_secs(60));
+        assert_eq!(flow.attempts_left("855", "12345678"), None);
+        flow.send_code(&client, "855", "12345678").await.unwrap();
+        assert_eq!(flow.attempts_left("855", "12345678"), Some(2));
+        for _ in 0..2 {
+            assert!(
+                flow.verify(&client, "855", "12345678", "000000")
+                    .await
+                    .is_err()
+            );
+        }
+        assert_eq!(flow.attempts_left("855", "12345678"), Some(0));
+        let error = flow
+            .verify(&client, "855", "12345678", "123456")
+            .await
+            .unwrap_err();
+        assert_eq!(login_error(error), SmsLoginError::TooManyAttempts);
+        assert_eq!(client.count(AUTH), 2);
+    }
+
+    #[test]
+    fn normalize() {
+        let expected = ("855".to_string(), "12345678".to_string());
+        assert_eq!(normalize_mobile("855", "12345678").unwrap(), expected);
+        assert_eq!(normalize_mobile("+855", "012 345-678").unwrap(), expected);
+        assert_eq!(
+            normalize_mobile("00855", "+855 12 345 678").unwrap(),
+            expected
+        );
+        assert_eq!(normalize_mobile("+855", "(0)12.345.678").unwrap(), expected);
+        assert!(normalize_mobile("+855", "+86 12345678").is_err());
+
+        let rome = ("39".to_string(), "0612345678".to_string());
+        assert_eq!(normalize_mobile("+39", "06 1234 5678").unwrap(), rome);
+        assert_eq!(normalize_mobile("0039", "+39 06 1234 5678").unwrap(), rome);
+        assert!(normalize_mobile("", "12345678").is_err());
+    }
+}
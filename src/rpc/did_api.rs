@@ -29,7 +29,7 @@
 ///     let sms_req = SmsCodeSendReq {
 ///         mobile: "1234567890".to_string(),
 ///         mobile_prefix: "86".to_string(),
-///         provider: "huione".to_string(),
+///         provider: Provider::Huione,
 ///         ..Default::default()
 ///     };
 ///     let sms_resp = client.send_code(sms_req).await?;
@@ -38,9 +38,9 @@
 ///     let auth_req = SmsAuthenticateReq {
 ///         mobile: "1234567890".to_string(),
 ///         mobile_prefix: "86".to_string(),
-///         sms_code: "123456".to_string(),
-///         code: sms_resp.data.unwrap(),
-///         provider: "huione".to_string(),
+///         sms_code: "123456".into(),
+///         code: sms_resp.into_result()?,
+///         provider: Provider::Huione,
 ///         ..Default::default()
 ///     };
 ///     let auth_resp = client.sms(auth_req).await?;
@@ -59,19 +59,19 @@
 ///     let nonce = get_nonce(&eph_pk_bytes, max_epoch, &jwt_randomness)?;
 ///
 ///     let authorize_req = AuthorizeTokenProfileReq {
-///         provider: "huione".to_string(),
+///         provider: Provider::Huione,
 ///         code: auth_resp.code,
-///         login_type: "sms".to_string(),
+///         login_type: LoginType::Sms,
 ///         nonce,
 ///     };
 ///
 ///     let authorize_resp= client.get_token(authorize_req).await?.get_data()?;
-///     client.set_header(ACCESS_TOKEN.to_string(),authorize_resp.access_token.clone());
+///     client.set_header(ACCESS_TOKEN.to_string(),authorize_resp.access_token.expose_secret().clone());
 ///
 ///     // 4. Get zk_proofs
 ///     let req = ZkProofsReq {
 ///         max_epoch: max_epoch as i64,
-///         jwt_randomness: jwt_randomness.clone(),
+///         jwt_randomness: jwt_randomness.clone().into(),
 ///         extended_ephemeral_public_key: kp_bigint.clone(),
 ///         jwt: authorize_resp.jwt_token.clone(),
 ///         salt: authorize_resp.salt.clone(),
@@ -82,8 +82,8 @@
 ///
 ///
 ///     // Calculate address_seed and address
-///     let (sub, aud, _) = parse_and_validate_jwt(&authorize_resp.jwt_token)?;
-///     let address_seed = gen_address_seed(&authorize_resp.salt, "sub", &sub, &aud)?;
+///     let (sub, aud, _) = parse_and_validate_jwt(authorize_resp.jwt_token.expose_secret())?;
+///     let address_seed = gen_address_seed(authorize_resp.salt.expose_secret(), "sub", &sub, &aud)?;
 ///     let zk_login_inputs = ZkLoginInputs::from_reader(zk_reader, &address_seed.to_string())?;
 ///     let pk = PublicKey::ZkLogin(ZkLoginPublicIdentifier::new(
 ///         zk_login_inputs.get_iss(),
@@ -101,7 +101,7 @@ use async_trait::async_trait;
 use fastcrypto_zkp::bn254::zk_login::ZkLoginInputsReader;
 
 #[async_trait]
-pub trait DIDApi: Call {
+pub trait DIDApi: Call + Send + Sync {
     const BASE_PATH: &'static str = "/did";
     /// Send mobile verification code
     ///
@@ -113,6 +113,7 @@ pub trait DIDApi: Call {
     /// # Return value
     /// Returns the result of sending the verification code. When successful, the data field contains the verification code identifier for subsequent authentication
     async fn send_code(&self, request: SmsCodeSendReq) -> Result<CommonResp<String>> {
+        self.check_request(&request)?;
         self.sign_call(
             CallMethod::Post,
             format!("{}/sendCode", Self::BASE_PATH),
@@ -132,6 +133,7 @@ pub trait DIDApi: Call {
     /// # Return value
     /// Returns the authentication result. When successful, it includes an authentication number used to obtain the JWT Token
     async fn sms(&self, request: SmsAuthenticateReq) -> Result<CommonResp<AuthenticateUserResp>> {
+        self.check_request(&request)?;
         self.call(
             CallMethod::Post,
             format!("{}/authenticateSms", Self::BASE_PATH),
@@ -154,6 +156,7 @@ pub trait DIDApi: Call {
         &self,
         request: AuthorizeTokenProfileReq,
     ) -> Result<CommonResp<AuthorizeTokenProfileResp>> {
+        self.check_request(&request)?;
         self.call(
             CallMethod::Post,
             format!("{}/getToken", Self::BASE_PATH),
@@ -176,6 +179,7 @@ pub trait DIDApi: Call {
         &self,
         request: RefreshJwtTokenReq,
     ) -> Result<CommonResp<AuthorizeTokenProfileResp>> {
+        self.check_request(&request)?;
         self.call(
             CallMethod::Post,
             format!("{}/refreshJwtToken", Self::BASE_PATH),
@@ -211,6 +215,7 @@ pub trait DIDApi: Call {
     /// # Return value
     /// Returns the ZK Login proof materials that can be used to generate a ZK Login authenticator for transaction signing
     async fn get_zk_proofs(&self, request: ZkProofsReq) -> Result<CommonResp<ZkLoginInputsReader>> {
+        self.check_request(&request)?;
         self.call(
             CallMethod::Post,
             format!("{}/getZkProofs", Self::BASE_PATH),
@@ -13,6 +13,7 @@ mod wallet_api;
 pub use wallet_api::*;
 
 use crate::types::common::CommonResp;
+use crate::types::validate::Validate;
 use crate::utils::rsa_sign::RSASign;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -31,6 +32,19 @@ pub enum CallMethod {
 
 #[async_trait]
 pub trait Call: RSASign {
+    /// Whether the API traits validate requests before sending them, enabled by default
+    fn validate_requests(&self) -> bool {
+        true
+    }
+
+    /// Validates a request unless validation is disabled
+    fn check_request<Req: Validate>(&self, req: &Req) -> anyhow::Result<()> {
+        if self.validate_requests() {
+            req.validate()?;
+        }
+        Ok(())
+    }
+
     async fn call<Req, Resp>(
         &self,
         method: CallMethod,
@@ -42,6 +56,30 @@ pub trait Call: RSASign {
         Req: Serialize + Debug + Send,
         Resp: for<'de> Deserialize<'de>;
 
+    /// Sends a request and returns the untyped response envelope
+    ///
+    /// Useful for endpoints not yet covered by the API traits, or to inspect a response whose
+    /// `data` does not match the SDK types. The request is not signed.
+    ///
+    /// The default implementation goes through [`Call::call`] and re-serializes the envelope,
+    /// so top-level fields unknown to [`CommonResp`] are lost; implementations with access to
+    /// the raw body should override it.
+    async fn call_raw<Req>(
+        &self,
+        method: CallMethod,
+        path: String,
+        header: Option<HashMap<String, String>>,
+        req: Option<Req>,
+    ) -> anyhow::Result<serde_json::Value>
+    where
+        Req: Serialize + Debug + Send,
+    {
+        let resp = self
+            .call::<Req, serde_json::Value>(method, path, header, req)
+            .await?;
+        Ok(serde_json::to_value(resp)?)
+    }
+
     async fn sign_call<Req, Resp>(
         &self,
         method: CallMethod,
@@ -53,3 +91,27 @@ pub trait Call: RSASign {
         Req: Serialize + Debug + Send,
         Resp: for<'de> Deserialize<'de>;
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Call, CallMethod};
+    use crate::testing::{MockClient, ok};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn default_call_raw() {
+        let client = MockClient::new(|_, body| ok(json!({"echo": body})));
+        let envelope = client
+            .call_raw(
+                CallMethod::Post,
+                "/custom".to_string(),
+                None,
+                Some(json!({"a": 1})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(envelope["code"], "000000");
+        assert_eq!(envelope["data"]["echo"]["a"], 1);
+        assert_eq!(client.count("/custom"), 1);
+    }
+}
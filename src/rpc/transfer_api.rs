@@ -7,7 +7,7 @@ use anyhow::Result;
 use async_trait::async_trait;
 
 #[async_trait]
-pub trait TransferApi: Call {
+pub trait TransferApi: Call + Send + Sync {
     const BASE_PATH: &'static str = "/transfer";
     /// Create a transfer order
     ///
@@ -17,6 +17,7 @@ pub trait TransferApi: Call {
     /// # Returns
     /// * `Result<CommonResp<CreateOrderResp>>` - The created order response
     async fn create_order(&self, req: TransferOrderReq) -> Result<CommonResp<CreateOrderResp>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/createOrder", Self::BASE_PATH),
@@ -37,6 +38,7 @@ pub trait TransferApi: Call {
         &self,
         req: TransferOrderTxReq,
     ) -> Result<CommonResp<TransferOrderTxResponse>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/sendTx", Self::BASE_PATH),
@@ -57,6 +59,7 @@ pub trait TransferApi: Call {
         &self,
         req: TransferOrderQueryPageReq,
     ) -> Result<CommonResp<PageResult<TransferOrderResp>>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/pageList", Self::BASE_PATH),
@@ -77,6 +80,7 @@ pub trait TransferApi: Call {
         &self,
         req: TransferOrderQueryReq,
     ) -> Result<CommonResp<TransferOrderResp>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/queryOrder", Self::BASE_PATH),
@@ -97,6 +101,7 @@ pub trait TransferApi: Call {
         &self,
         req: BuildSponsorTxReq,
     ) -> Result<CommonResp<GasTxBuilderResponse>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/buildSponsorTransaction", Self::BASE_PATH),
@@ -114,6 +119,7 @@ pub trait TransferApi: Call {
     /// # Returns
     /// * `Result<CommonResp<ProxyPayTxResp>>` - The proxy payment transaction response
     async fn do_proxy_pay_tx(&self, req: ProxyPayTxReq) -> Result<CommonResp<ProxyPayTxResp>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/doProxyPayTx", Self::BASE_PATH),
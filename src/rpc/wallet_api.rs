@@ -9,7 +9,7 @@ use async_trait::async_trait;
 /// This trait provides methods for interacting with wallet-related functionalities,
 /// including querying chain currency information and user wallet details.
 #[async_trait]
-pub trait WalletApi: Call {
+pub trait WalletApi: Call + Send + Sync {
     const BASE_PATH: &'static str = "/wallet";
 
     /// Retrieves a list of supported chain currencies
@@ -44,6 +44,7 @@ pub trait WalletApi: Call {
         &self,
         req: QueryWalletReq,
     ) -> anyhow::Result<CommonResp<Vec<UserWalletResp>>> {
+        self.check_request(&req)?;
         self.call(
             CallMethod::Post,
             format!("{}/queryUserWalletForList", Self::BASE_PATH),
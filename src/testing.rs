@@ -0,0 +1,263 @@
+//! Helpers shared by unit tests
+//!
+//! [`TestServer`] is a minimal HTTP server answering with canned JSON responses, used to
+//! exercise [`OneChainWalletService`](crate::client::OneChainWalletService) end to end.
+//! [`MockClient`] implements [`Call`] in memory for code built on top of the API traits.
+use crate::client::metrics::AttemptCountMiddleware;
+use crate::rpc::{Call, CallMethod};
+use crate::types::common::CommonResp;
+use crate::utils::rsa_sign::RSASign;
+use async_trait::async_trait;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::RetryTransientMiddleware;
+use reqwest_retry::policies::ExponentialBackoff;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// 2048-bit test key pair, PKCS#8 private key and SPKI public key as base64 DER
+pub(crate) const PUB_KEY: &'static str = "MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAr43KS8cko41MYEyDAlwqm3t9JRmBtTQQnm7l+RzrBCvPODRmpZGNhpO2MUgVFYdWkHlt/zTEGAqkhDUXkkwpeHebB9zWhTbhDGEdohW5T82MtdihNGgemoeNpC/eTt46o/5nqHzbe84CNhefEQdVMmYJcnX2Ma/g5VzFXOjOM7/ThE02L4TIMAjsFhapXRMcxZ4i0D2Xn0HVtl2uEURdXdQHnoAKjoGHukV4S/olMw8B6u2N0TpjJt9ORKCvIBYvsXgyVVcUzMLmUDIiS+RbhqZ60R9bTDeYSzm8ej/WgRM0ap6U89DDUvtEN1atb00rKqW+aU/ob0FU83Q2LeLq9QIDAQAB";
+pub(crate) const PRI_KEY: &'static str = "MIIEvQIBADANBgkqhkiG9w0BAQEFAASCBKcwggSjAgEAAoIBAQCvjcpLxySjjUxgTIMCXCqbe30lGYG1NBCebuX5HOsEK884NGalkY2Gk7YxSBUVh1aQeW3/NMQYCqSENReSTCl4d5sH3NaFNuEMYR2iFblPzYy12KE0aB6ah42kL95O3jqj/meofNt7zgI2F58RB1UyZglydfYxr+DlXMVc6M4zv9OETTYvhMgwCOwWFqldExzFniLQPZefQdW2Xa4RRF1d1AeegAqOgYe6RXhL+iUzDwHq7Y3ROmMm305EoK8gFi+xeDJVVxTMwuZQMiJL5FuGpnrRH1tMN5hLObx6P9aBEzRqnpTz0MNS+0Q3Vq1vTSsqpb5pT+hvQVTzdDYt4ur1AgMBAAECggEAAgw0WEo3pZ3evFX12KsO1L27kvTHWdIo7uS6QSBSy7uEOkBRE+fjuNshpZ5eDSmFG2TfM3D/+kKrO7pmzrLCJ1xIEspnpHL/2dz4s18mWqDxAoMif1+QGq2dO8MuCDbmg+rkdlmmeuGisveuI3FsmIycbHSlyKSVifdZMfyqUxB3ysLl4SQLxoZ2x4NL9e/Jj8NlKKgLZeXgqp4/ojh3IUGwHIYnz2PVm+K55wbq55E61p3yyd+09kIOajqLx+d6CsfNY9MhOXdl9W7vZEGKeQ1HuCQ9muwEAmO32yELQC39t4Q7GuPD+0shTMmDs6QsUXzZC/XfqBd3xPBlwkvIgQKBgQDXiUNMfctRYQuFkLR3Ux+rpAxCOxLLdGjoSyVJaZzeAci5U28R4CbVeVV1HeRmy+x2kwe1YD/7x6qCxQUlRnDYATcZJf09YrrrZFXPQilCTqi2RdWy1Zq0M9sEhFWsJL4QF0fF/puXwXVbRB/uVMbH/jyT5wFNxbmmNxWixtK41QKBgQDQgvbxxDdc+WSWnAj0uTsiDloewmeueh/IdnGTPSx5qfF931VeWl4waOqhI8N6sDEYhvMa8+XjDdJZ08YdPh9bPQIhNCcEbL2u9SEt2VZ7nx/oVPQCyBIHsXaOoPtPH68qnTlSPhDajZALhPQVQwpxizmTfVuyi/hZG1OsYgB5oQKBgQCGva6uwO074JkdVIsdFX/1A0cOmHN1cT6sCV4z+KwyNZdQFBKZcDGWvpVn89n3UYBv2Ba3koYtVnMH8Tb4SIL+5jOVqyQXHgOQaFckjE3Sv+3ElP+1Hsfp44kF19zfEtEmqgcahcKrKiu9dGcpzSG/oPYp1/3+qp8Wg9Uov3a4SQKBgDT17s89rWo6FiiC/WtbWP+vcYh6jGcusb/zBaoGUbOdTK9R+Jb8kQvuuhmvwcj5056NOFZSOMPREOqr9Zgb3U8JUe8pFffzvsIflQvWNjc0FaCnY0sJkjrOAnT7wpk4TP+f651OEm3QoxOp820rGA369ObXYmEZWD0ZycjxI3nBAoGANwAhsbfdNuzl6wHHzjRu4kmZJhOSnK1/aHlBpXc/ynBR+BVBghHseKm50azOp6Tx3D19zgViaXGWiH/x3wr2qab5Jy33njx3VUu1r0lug9PoOfzhZ3HqfMT7hAqnuZDn4Ey/t4fzIn38o9yPd8tvZkLnMqZPGR6bE4kLYfforAs=";
+
+/// Successful response envelope carrying `data`
+pub(crate) fn ok(data: Value) -> Value {
+    json!({"code": "000000", "msg": "success", "success": true, "data": data})
+}
+
+/// Failed response envelope
+pub(crate) fn fail(code: &str, msg: &str) -> Value {
+    json!({"code": code, "msg": msg, "success": false})
+}
+
+/// HTTP client retrying transient errors without noticeable backoff
+pub(crate) fn retry_client(max_retries: u32) -> ClientWithMiddleware {
+    let policy = ExponentialBackoff::builder()
+        .retry_bounds(Duration::from_millis(1), Duration::from_millis(5))
+        .build_with_max_retries(max_retries);
+    ClientBuilder::new(reqwest::Client::new())
+        .with(RetryTransientMiddleware::new_with_policy(policy))
+        .with(AttemptCountMiddleware)
+        .build()
+}
+
+/// Request received by a [`TestServer`]
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedRequest {
+    pub path: String,
+    /// Headers with lower case names
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+/// HTTP server answering every request with the next canned response
+pub(crate) struct TestServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl TestServer {
+    /// Serves `responses` in order, repeating the last one once all were served
+    pub async fn start(responses: Vec<(u16, Value)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let mut responses = responses.into_iter();
+            let mut last = (200, Value::Null);
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let Some(request) = read_request(&mut stream).await else {
+                    continue;
+                };
+                recorded
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(request);
+                if let Some(response) = responses.next() {
+                    last = response;
+                }
+                let body = last.1.to_string();
+                let head = format!(
+                    "HTTP/1.1 {} Test\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    last.0,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(body.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// Requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let path = lines.next()?.split(' ').nth(1)?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect::<HashMap<_, _>>();
+    let len = headers
+        .get("content-length")
+        .and_then(|len| len.parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + len {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Some(RecordedRequest {
+        path,
+        headers,
+        body: String::from_utf8_lossy(&buf[header_end..]).into_owned(),
+    })
+}
+
+type Handler = dyn Fn(&str, &Value) -> Value + Send + Sync;
+
+/// In-memory [`Call`] implementation answering with envelopes built by a handler
+///
+/// The handler receives the path and the JSON request body, `Value::Null` without body, and
+/// returns the response envelope, e.g. built with [`ok`] or [`fail`].
+pub(crate) struct MockClient {
+    handler: Box<Handler>,
+    delay: Duration,
+    calls: Mutex<Vec<(String, Value)>>,
+}
+
+impl MockClient {
+    pub fn new(handler: impl Fn(&str, &Value) -> Value + Send + Sync + 'static) -> Self {
+        Self {
+            handler: Box::new(handler),
+            delay: Duration::ZERO,
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Delays every response, so concurrent calls overlap
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Paths and request bodies of the calls made so far
+    pub fn calls(&self) -> Vec<(String, Value)> {
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Number of calls made to `path`
+    pub fn count(&self, path: &str) -> usize {
+        self.calls().iter().filter(|(p, _)| p == path).count()
+    }
+
+    async fn respond<Req, Resp>(
+        &self,
+        path: String,
+        req: Option<Req>,
+    ) -> anyhow::Result<CommonResp<Resp>>
+    where
+        Req: Serialize,
+        Resp: for<'de> Deserialize<'de>,
+    {
+        let body = match req {
+            Some(req) => serde_json::to_value(req)?,
+            None => Value::Null,
+        };
+        self.calls
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((path.clone(), body.clone()));
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        CommonResp::decode_value((self.handler)(&path, &body), false)
+    }
+}
+
+impl Debug for MockClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockClient").finish_non_exhaustive()
+    }
+}
+
+impl RSASign for MockClient {
+    fn sign<T: Serialize + Debug>(
+        &self,
+        _obj: &T,
+        _ignore_fields: Vec<&str>,
+    ) -> anyhow::Result<String> {
+        Ok("sign".to_string())
+    }
+}
+
+#[async_trait]
+impl Call for MockClient {
+    async fn call<Req, Resp>(
+        &self,
+        _method: CallMethod,
+        path: String,
+        _header: Option<HashMap<String, String>>,
+        req: Option<Req>,
+    ) -> anyhow::Result<CommonResp<Resp>>
+    where
+        Req: Serialize + Debug + Send,
+        Resp: for<'de> Deserialize<'de>,
+    {
+        self.respond(path, req).await
+    }
+
+    async fn sign_call<Req, Resp>(
+        &self,
+        _method: CallMethod,
+        path: String,
+        _header: Option<HashMap<String, String>>,
+        req: Option<Req>,
+    ) -> anyhow::Result<CommonResp<Resp>>
+    where
+        Req: Serialize + Debug + Send,
+        Resp: for<'de> Deserialize<'de>,
+    {
+        self.respond(path, req).await
+    }
+}
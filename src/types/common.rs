@@ -1,9 +1,109 @@
+use crate::sui_types::digests::TransactionDigest;
+use crate::types::error::{ApiError, EmptyDataError};
+use crate::types::timestamp::Timestamp;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 use std::time::SystemTime;
+use zeroize::Zeroize;
 
 pub const ACCESS_TOKEN: &'static str = "ACCESS_TOKEN";
 pub const TOKEN_ID: &'static str = "TOKEN_ID";
+/// Header carrying the id of the merchant key used for `merchantSign`
+///
+/// Opt-in: the header is not part of the documented server API and is only sent for keys
+/// registered with an id, see [`MerchantKeyRing::with_key_id`](crate::utils::key_ring::MerchantKeyRing::with_key_id).
+/// Only use key ids once the server has confirmed that it honours this header.
+pub const MERCHANT_KEY_ID: &'static str = "MERCHANT_KEY_ID";
+/// Header carrying the link string canonicalization version of `merchantSign`
+pub const SIGN_VERSION: &'static str = "SIGN_VERSION";
+
+/// Response fields not known to this SDK version, collected by `#[serde(flatten)]`
+///
+/// Every response type keeps unknown fields in an `extra` map, so fields added by the server
+/// are preserved instead of being dropped silently.
+pub type ExtraFields = serde_json::Map<String, serde_json::Value>;
+
+/// Wrapper for sensitive values such as tokens, salts and verification codes
+///
+/// The wrapped value is serialized transparently, so it has no effect on the wire format or
+/// on the merchant signature. `Debug` output is redacted and the value is zeroized on drop.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns a reference to the wrapped value
+    ///
+    /// Callers are responsible for not logging or persisting the returned value.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Deserializes an optional string-encoded value, treating an empty string as absent
+///
+/// For response fields such as addresses that the server may send as `""` when unset, use
+/// with `#[serde(default, deserialize_with = "empty_as_none")]`.
+pub fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+/// Serializes an optional value in its `Display` form, `None` as an empty string
+///
+/// Counterpart of [`empty_as_none`] for request fields that the server receives as `""` when
+/// unset, use with `#[serde(serialize_with = "none_as_empty")]`.
+pub fn none_as_empty<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Display,
+{
+    match value {
+        Some(value) => serializer.collect_str(value),
+        None => serializer.serialize_str(""),
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -39,32 +139,130 @@ pub struct CommonResp<T = String> {
     /// Status code
     pub code: String,
     /// Response message
+    #[serde(default)]
     pub msg: String,
     /// Response data
     pub data: Option<T>,
     /// Whether successful
     pub success: bool,
+    #[serde(default)]
     pub trace_id: String,
-    pub system_time: i64,
+    /// Server time
+    #[serde(default)]
+    pub system_time: Timestamp,
 }
 
 impl<T> CommonResp<T> {
     pub const SUCCESS_RESP_CODE: &'static str = "000000";
     pub const SUCCESS_MSG: &'static str = "success";
+
+    /// Whether the response reports success with the success code
+    pub fn is_success(&self) -> bool {
+        self.success && self.code == Self::SUCCESS_RESP_CODE
+    }
+
+    /// Returns the error of a failed response
+    ///
+    /// A response flagged as successful but carrying a code other than
+    /// [`SUCCESS_RESP_CODE`](Self::SUCCESS_RESP_CODE) is reported as failed as well.
+    pub fn error(&self) -> Option<ApiError> {
+        if self.is_success() {
+            return None;
+        }
+        Some(ApiError {
+            raw_code: self.code.clone(),
+            msg: self.msg.clone(),
+            trace_id: self.trace_id.clone(),
+        })
+    }
+
+    /// Returns the data of a successful response, `None` if it carries no data
+    ///
+    /// Fails with an [`ApiError`] if the response is not successful.
+    pub fn data_opt(&self) -> anyhow::Result<Option<&T>> {
+        match self.error() {
+            Some(error) => Err(error.into()),
+            None => Ok(self.data.as_ref()),
+        }
+    }
+
+    /// Consumes the response and returns its data
+    ///
+    /// Fails with an [`ApiError`] if the response is not successful, and with an
+    /// [`EmptyDataError`] if a successful response carries no data.
+    pub fn into_result(self) -> anyhow::Result<T> {
+        if let Some(error) = self.error() {
+            return Err(error.into());
+        }
+        let trace_id = self.trace_id;
+        self.data.ok_or_else(|| EmptyDataError { trace_id }.into())
+    }
+
+    /// Consumes the response and returns its data, for endpoints whose data may be absent
+    ///
+    /// Fails with an [`ApiError`] if the response is not successful.
+    pub fn into_option(self) -> anyhow::Result<Option<T>> {
+        match self.error() {
+            Some(error) => Err(error.into()),
+            None => Ok(self.data),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> CommonResp<T> {
+    /// Decodes a response body
+    ///
+    /// Response types already accept missing or `null` descriptive fields, so routine schema
+    /// drift decodes in strict mode without losing data. Lenient mode is a last resort: a `data`
+    /// field that still does not match `T` is logged and dropped instead of failing, so the code
+    /// and message of the envelope remain available. The envelope itself must be well-formed.
+    pub fn decode(body: &[u8], lenient: bool) -> anyhow::Result<Self> {
+        if !lenient {
+            return Ok(serde_json::from_slice(body)?);
+        }
+        Self::decode_value(serde_json::from_slice(body)?, lenient)
+    }
+
+    /// Decodes a response envelope that has already been parsed as JSON, see [`CommonResp::decode`]
+    pub fn decode_value(body: serde_json::Value, lenient: bool) -> anyhow::Result<Self> {
+        if !lenient {
+            return Ok(serde_json::from_value(body)?);
+        }
+        let error = match serde_json::from_value::<Self>(body.clone()) {
+            Ok(resp) => return Ok(resp),
+            Err(error) => error,
+        };
+        let Ok(envelope) = serde_json::from_value::<CommonResp<serde_json::Value>>(body) else {
+            return Err(error.into());
+        };
+        tracing::warn!(
+            %error,
+            code = %envelope.code,
+            trace_id = %envelope.trace_id,
+            "response data does not match the expected schema and was dropped"
+        );
+        Ok(CommonResp {
+            code: envelope.code,
+            msg: envelope.msg,
+            data: None,
+            success: envelope.success,
+            trace_id: envelope.trace_id,
+            system_time: envelope.system_time,
+        })
+    }
 }
 
 impl<T: Clone> CommonResp<T> {
+    /// Returns a copy of the data of a successful response
+    ///
+    /// Prefer [`CommonResp::into_result`], which does not clone.
     pub fn get_data(&self) -> anyhow::Result<T> {
-        if self.success {
-            Ok(self.data.clone().unwrap())
-        } else {
-            anyhow::bail!(
-                "code: {} msg:{} trace:{}",
-                self.code.clone(),
-                self.msg.clone(),
-                self.trace_id.clone()
-            )
-        }
+        self.data_opt()?.cloned().ok_or_else(|| {
+            EmptyDataError {
+                trace_id: self.trace_id.clone(),
+            }
+            .into()
+        })
     }
 }
 
@@ -72,15 +270,19 @@ impl<T: Clone> CommonResp<T> {
 #[serde(rename_all = "camelCase")]
 pub struct CreateOrderResp {
     /// Transaction hash
-    pub hash: String,
+    pub hash: TransactionDigest,
     /// Raw transaction data to be signed
     pub raw_transaction: String,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PageResult<T = String> {
     /// Paged data
+    #[serde(default = "Vec::new")]
     pub rows: Vec<T>,
     /// Total number
     pub total_num: i64,
@@ -88,4 +290,63 @@ pub struct PageResult<T = String> {
     pub page_size: i64,
     /// Current page index
     pub page_index: i64,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resp(code: &str, success: bool, data: Option<String>) -> CommonResp<String> {
+        CommonResp {
+            code: code.to_string(),
+            msg: "msg".to_string(),
+            data,
+            success,
+            trace_id: "trace".to_string(),
+            system_time: Timestamp::from_millis(0),
+        }
+    }
+
+    #[test]
+    fn into_result() {
+        let ok = resp("000000", true, Some("data".to_string()));
+        assert_eq!(ok.data_opt().unwrap(), Some(&"data".to_string()));
+        assert_eq!(ok.into_result().unwrap(), "data");
+
+        let empty = resp("000000", true, None);
+        assert!(empty.data_opt().unwrap().is_none());
+        let err = empty.into_result().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<EmptyDataError>().unwrap().trace_id,
+            "trace"
+        );
+
+        let failed = resp("200001", false, None);
+        let err = failed.into_result().unwrap_err();
+        let err = err.downcast_ref::<ApiError>().unwrap();
+        assert_eq!(err.raw_code, "200001");
+
+        let inconsistent = resp("999999", true, Some("data".to_string()));
+        assert_eq!(inconsistent.error().unwrap().raw_code, "999999");
+    }
+
+    #[test]
+    fn decode() {
+        let body = br#"{"code":"000000","success":true,"traceId":"trace",
+            "data":{"rows":[],"totalNum":0,"pageSize":10,"pageIndex":1,"nextCursor":"abc"}}"#;
+        let resp = CommonResp::<PageResult>::decode(body, false).unwrap();
+        assert_eq!(resp.msg, "");
+        assert_eq!(resp.into_result().unwrap().extra["nextCursor"], "abc");
+
+        let body = br#"{"code":"000000","success":true,"traceId":"trace","data":{"rows":1}}"#;
+        assert!(CommonResp::<PageResult>::decode(body, false).is_err());
+        let resp = CommonResp::<PageResult>::decode(body, true).unwrap();
+        assert!(resp.is_success());
+        assert!(resp.data.is_none());
+
+        assert!(CommonResp::<PageResult>::decode(b"<html>", true).is_err());
+    }
 }
@@ -1,7 +1,137 @@
+use crate::types::common::{ExtraFields, Secret};
+use crate::types::timestamp::Timestamp;
 use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Authentication provider
+///
+/// Serialized as its lowercase name; values unknown to this SDK version round-trip through
+/// [`Provider::Custom`].
+///
+/// Only `huione` is confirmed by the platform. The wire values of [`Provider::Google`],
+/// [`Provider::Apple`] and [`Provider::Telegram`] are unverified; if the server names them
+/// differently, send the expected value with [`Provider::Custom`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Provider {
+    #[default]
+    Huione,
+    /// Sent as `google`, unverified
+    Google,
+    /// Sent as `apple`, unverified
+    Apple,
+    /// Sent as `telegram`, unverified
+    Telegram,
+    Custom(String),
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Provider::Huione => "huione",
+            Provider::Google => "google",
+            Provider::Apple => "apple",
+            Provider::Telegram => "telegram",
+            Provider::Custom(provider) => provider,
+        }
+    }
+}
+
+impl From<String> for Provider {
+    fn from(provider: String) -> Self {
+        match provider.as_str() {
+            "huione" => Provider::Huione,
+            "google" => Provider::Google,
+            "apple" => Provider::Apple,
+            "telegram" => Provider::Telegram,
+            _ => Provider::Custom(provider),
+        }
+    }
+}
+
+impl From<&str> for Provider {
+    fn from(provider: &str) -> Self {
+        provider.to_string().into()
+    }
+}
+
+impl From<Provider> for String {
+    fn from(provider: Provider) -> Self {
+        match provider {
+            Provider::Custom(provider) => provider,
+            provider => provider.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for Provider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Login type of an authorization code
+///
+/// Serialized as its lowercase name; values unknown to this SDK version round-trip through
+/// [`LoginType::Custom`].
+///
+/// Only `sms` is confirmed by the platform. The wire value of [`LoginType::Oauth`] is
+/// unverified; if the server names it differently, send the expected value with
+/// [`LoginType::Custom`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum LoginType {
+    /// Code obtained from [`DIDApi::sms`](crate::rpc::DIDApi::sms)
+    #[default]
+    Sms,
+    /// Code obtained from an OAuth authorization-code redirect, sent as `oauth`, unverified
+    Oauth,
+    Custom(String),
+}
+
+impl LoginType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LoginType::Sms => "sms",
+            LoginType::Oauth => "oauth",
+            LoginType::Custom(login_type) => login_type,
+        }
+    }
+}
+
+impl From<String> for LoginType {
+    fn from(login_type: String) -> Self {
+        match login_type.as_str() {
+            "sms" => LoginType::Sms,
+            "oauth" => LoginType::Oauth,
+            _ => LoginType::Custom(login_type),
+        }
+    }
+}
+
+impl From<&str> for LoginType {
+    fn from(login_type: &str) -> Self {
+        login_type.to_string().into()
+    }
+}
+
+impl From<LoginType> for String {
+    fn from(login_type: LoginType) -> Self {
+        match login_type {
+            LoginType::Custom(login_type) => login_type,
+            login_type => login_type.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for LoginType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 /// Request for obtaining authorization token
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthorizeTokenProfileReq {
     /// Authorization code
@@ -11,13 +141,13 @@ pub struct AuthorizeTokenProfileReq {
     /// Required field, cannot be empty
     pub nonce: String,
     /// Authentication client type, default is huione
-    pub provider: String,
+    pub provider: Provider,
     /// Login type, for example: sms
-    pub login_type: String,
+    pub login_type: LoginType,
 }
 
 /// Mobile phone authorization request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SmsAuthenticateReq {
     /// Phone number prefix
@@ -31,13 +161,13 @@ pub struct SmsAuthenticateReq {
     pub code: String,
     /// SMS verification code
     /// Required field, cannot be empty
-    pub sms_code: String,
+    pub sms_code: Secret<String>,
     /// Access channel: huione
-    pub provider: String,
+    pub provider: Provider,
 }
 
 /// Send SMS verification code request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SmsCodeSendReq {
     /// Mobile phone number
@@ -47,7 +177,7 @@ pub struct SmsCodeSendReq {
     /// Required field, cannot be empty
     pub mobile_prefix: String,
     /// Channel, default is huione
-    pub provider: String,
+    pub provider: Provider,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +185,9 @@ pub struct SmsCodeSendReq {
 pub struct AuthenticateUserResp {
     /// Authentication number
     pub code: String,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,14 +203,20 @@ pub struct AccessTokenProfile {
     pub sub: String,
     /// Random value
     pub nonce: String,
-    /// Token effective time
-    pub nbf: i64,
-    /// Token issuance time
-    pub iat: i64,
-    /// Token expiration time
-    pub exp: i64,
+    /// Token effective time, sent in seconds as defined for JWT claims
+    #[serde(with = "crate::types::timestamp::secs")]
+    pub nbf: Timestamp,
+    /// Token issuance time, sent in seconds as defined for JWT claims
+    #[serde(with = "crate::types::timestamp::secs")]
+    pub iat: Timestamp,
+    /// Token expiration time, sent in seconds as defined for JWT claims
+    #[serde(with = "crate::types::timestamp::secs")]
+    pub exp: Timestamp,
     /// Token unique identifier
     pub jti: String,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,10 +225,11 @@ pub struct AuthorizeTokenProfileResp {
     /// Token information
     pub access_token_profile: AccessTokenProfile,
     /// Access Token KEY=ACCESS_TOKEN
-    pub access_token: String,
+    pub access_token: Secret<String>,
     /// JWT-Token
-    pub jwt_token: String,
+    pub jwt_token: Secret<String>,
     /// Whether payment password is set
+    #[serde(default)]
     pub setting_pay_password: bool,
     /// Avatar
     pub avatar_url: Option<String>,
@@ -98,32 +238,39 @@ pub struct AuthorizeTokenProfileResp {
     /// Did
     pub did: Option<String>,
     /// User salt value
-    pub salt: String,
+    pub salt: Secret<String>,
     /// Whether anonymous
+    #[serde(default)]
     pub anonymous: bool,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserTokenProfile {
     /// Expiration time
-    pub expire_time: i64,
+    pub expire_time: Timestamp,
     /// Username
-    pub user_name: String,
+    pub user_name: Option<String>,
     /// Avatar
-    pub avatar: String,
+    pub avatar: Option<String>,
     /// ID
     pub id: i64,
     /// Channel user number
-    pub channel_user_no: String,
+    pub channel_user_no: Option<String>,
     /// User number
     pub user_no: String,
     /// Access token
-    pub access_token: String,
+    pub access_token: Secret<String>,
     /// Provider hc
-    pub provider: String,
+    pub provider: Provider,
     /// DID
-    pub did: String,
+    pub did: Option<String>,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 /// Request for obtaining ZK proof
@@ -135,26 +282,86 @@ pub struct ZkProofsReq {
     pub max_epoch: i64,
     /// JWT randomness
     /// Required field, cannot be empty
-    pub jwt_randomness: String,
+    pub jwt_randomness: Secret<String>,
     /// Temporary public key
     /// Required field, cannot be empty
     pub extended_ephemeral_public_key: String,
     /// JWT-token information
     /// Required field, cannot be empty
-    pub jwt: String,
+    pub jwt: Secret<String>,
     /// Salt value
     /// Required field, cannot be empty
-    pub salt: String,
+    pub salt: Secret<String>,
     /// JWT-sub information, user number
     /// Required field, cannot be empty
     pub key_claim_name: String,
 }
 
+impl Default for ZkProofsReq {
+    /// Defaults `key_claim_name` to `sub`, the claim used by the platform
+    fn default() -> Self {
+        Self {
+            max_epoch: 0,
+            jwt_randomness: Secret::default(),
+            extended_ephemeral_public_key: String::new(),
+            jwt: Secret::default(),
+            salt: Secret::default(),
+            key_claim_name: "sub".to_string(),
+        }
+    }
+}
+
 /// Refresh token request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RefreshJwtTokenReq {
     /// Random string
     /// Required field, cannot be empty
     pub nonce: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn provider_serde() {
+        let req = AuthorizeTokenProfileReq {
+            provider: Provider::Custom("line".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["provider"], "line");
+        assert_eq!(json["loginType"], "sms");
+
+        let provider = serde_json::from_str::<Provider>(r#""google""#).unwrap();
+        assert_eq!(provider, Provider::Google);
+        let provider = serde_json::from_str::<Provider>(r#""line""#).unwrap();
+        assert_eq!(provider, Provider::Custom("line".to_string()));
+    }
+
+    #[test]
+    fn secrets_redacted() {
+        let req = ZkProofsReq {
+            jwt_randomness: "randomness-value".into(),
+            jwt: "jwt-value".into(),
+            salt: "salt-value".into(),
+            ..Default::default()
+        };
+        let debug = format!("{:?}", req);
+        assert!(debug.contains("[REDACTED]"));
+        for secret in ["randomness-value", "jwt-value", "salt-value"] {
+            assert!(!debug.contains(secret), "{} leaked", secret);
+        }
+        // the wire format is unchanged
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["jwt"], "jwt-value");
+        assert_eq!(json["salt"], "salt-value");
+
+        let req = SmsAuthenticateReq {
+            sms_code: "123456".into(),
+            ..Default::default()
+        };
+        assert!(!format!("{:?}", req).contains("123456"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Business errors and response decoding errors
+//!
+//! A failed [`CommonResp`](crate::types::common::CommonResp) is turned into an [`ApiError`] by
+//! [`CommonResp::into_result`](crate::types::common::CommonResp::into_result) and the other
+//! accessors. The error travels inside `anyhow::Error` and can be recovered with
+//! `downcast_ref` to inspect the response code:
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::rpc::DIDApi;
+//! use onechain_wallet_rust_sdk::types::did::SmsAuthenticateReq;
+//! use onechain_wallet_rust_sdk::types::error::ApiError;
+//!
+//! async fn example<T: DIDApi>(client: &T, req: SmsAuthenticateReq) -> anyhow::Result<()> {
+//!     match client.sms(req).await?.into_result() {
+//!         Ok(resp) => println!("{}", resp.code),
+//!         Err(err) => match err.downcast_ref::<ApiError>() {
+//!             Some(api_error) => println!("rejected with code {}", api_error.raw_code),
+//!             None => return Err(err),
+//!         },
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::fmt::{Debug, Display, Formatter};
+
+/// Failed API response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// Response code as returned by the server
+    pub raw_code: String,
+    /// Response message
+    pub msg: String,
+    pub trace_id: String,
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "code: {} msg:{} trace:{}",
+            self.raw_code, self.msg, self.trace_id
+        )
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Successful API response that unexpectedly carries no data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyDataError {
+    pub trace_id: String,
+}
+
+impl Display for EmptyDataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "successful response without data trace:{}",
+            self.trace_id
+        )
+    }
+}
+
+impl std::error::Error for EmptyDataError {}
+
+/// Response body that could not be decoded
+///
+/// Keeps the HTTP status, headers and raw body, which are otherwise lost when the body does not
+/// match the expected schema or is not JSON at all, e.g. an HTML error page from a gateway.
+///
+/// The body and headers may carry tokens, so neither `Debug` nor `Display` prints them in full:
+/// JSON bodies are redacted, other bodies are truncated to [`DecodeError::EXCERPT_LEN`]
+/// characters and credential headers are redacted. Read the public fields to inspect them.
+pub struct DecodeError {
+    /// Endpoint path
+    pub path: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Response headers
+    pub headers: http::HeaderMap,
+    /// Raw response body, invalid UTF-8 sequences are replaced
+    pub body: String,
+    /// Underlying decoding error
+    pub error: anyhow::Error,
+}
+
+impl DecodeError {
+    /// Maximum number of characters of a non-JSON body printed by `Debug`
+    pub const EXCERPT_LEN: usize = 200;
+
+    /// Body as printed by `Debug`
+    fn body_excerpt(&self) -> String {
+        if serde_json::from_str::<serde_json::Value>(&self.body).is_ok() {
+            return format!("<{} bytes of JSON redacted>", self.body.len());
+        }
+        match self.body.char_indices().nth(Self::EXCERPT_LEN) {
+            Some((end, _)) => format!("{}... ({} bytes)", &self.body[..end], self.body.len()),
+            None => self.body.clone(),
+        }
+    }
+}
+
+/// Whether a header may carry credentials
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["authorization", "cookie", "token", "secret", "key"]
+        .iter()
+        .any(|part| name.contains(part))
+}
+
+impl Debug for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_sensitive_header(name.as_str()) {
+                    "<redacted>"
+                } else {
+                    value.to_str().unwrap_or("<binary>")
+                };
+                (name.as_str(), value)
+            })
+            .collect::<Vec<_>>();
+        f.debug_struct("DecodeError")
+            .field("path", &self.path)
+            .field("status", &self.status)
+            .field("headers", &headers)
+            .field("body", &self.body_excerpt())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "failed to decode response of {} with status {}: {}",
+            self.path, self.status, self.error
+        )
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DecodeError;
+
+    fn decode_error(body: &str) -> DecodeError {
+        let mut headers = http::HeaderMap::new();
+        headers.insert("content-type", "text/html".parse().unwrap());
+        headers.insert("set-cookie", "session=secret-cookie".parse().unwrap());
+        headers.insert("access_token", "secret-token".parse().unwrap());
+        DecodeError {
+            path: "/did/getToken".to_string(),
+            status: 502,
+            headers,
+            body: body.to_string(),
+            error: anyhow::anyhow!("expected value"),
+        }
+    }
+
+    #[test]
+    fn debug_redacts_body_and_headers() {
+        let debug = format!(
+            "{:?}",
+            decode_error(r#"{"data":{"accessToken":"secret-jwt"}}"#)
+        );
+        assert!(debug.contains("text/html"));
+        assert!(debug.contains("bytes of JSON redacted"));
+        assert!(!debug.contains("secret"));
+
+        let page = format!("<html>{}</html>", "x".repeat(1000));
+        let debug = format!("{:?}", decode_error(&page));
+        assert!(debug.contains("<html>xxx"));
+        assert!(!debug.contains("</html>"));
+
+        let display = decode_error(&page).to_string();
+        assert!(display.contains("/did/getToken") && display.contains("502"));
+        assert!(!display.contains("<html>"));
+    }
+}
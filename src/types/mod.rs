@@ -20,6 +20,18 @@
 //!   - Contains response types for transfer status and results
 //!   - Supports multi-currency, multi-chain transfer operations
 //!
+//! * [`error`] - Business error types
+//!   - Defines [`ApiError`](error::ApiError) returned for failed responses
+//!   - Keeps status, headers and body of undecodable responses in [`DecodeError`](error::DecodeError)
+//!
+//! * [`timestamp`] - Time types
+//!   - Defines the millisecond timestamp [`Timestamp`](timestamp::Timestamp) used by all request and response types
+//!   - Provides range builders such as [`TimeRange::last_days`](timestamp::TimeRange::last_days)
+//!
+//! * [`validate`] - Client-side request validation
+//!   - Defines the [`Validate`](validate::Validate) trait implemented by all request types
+//!   - Reports invalid fields as [`ValidationError`](validate::ValidationError)
+//!
 //! * [`wallet`] - Wallet related types
 //!   - Defines chain and currency information structure [`CurrencyChainResp`](wallet::CurrencyChainResp)
 //!   - Contains detailed currency information [`CurrencyInfo`](wallet::CurrencyInfo)
@@ -31,12 +43,12 @@
 //!
 //! ```no_run
 //! use onechain_wallet_rust_sdk::types::common::BaseReq;
-//! use onechain_wallet_rust_sdk::types::did::SmsCodeSendReq;
+//! use onechain_wallet_rust_sdk::types::did::{Provider, SmsCodeSendReq};
 //!
 //! let req = SmsCodeSendReq {
 //!     mobile: "12345678".to_string(),
 //!     mobile_prefix: "855".to_string(),
-//!     provider: "huione".to_string(),
+//!     provider: Provider::Huione,
 //!     ..Default::default()
 //! };
 //!
@@ -45,5 +57,8 @@
 
 pub mod common;
 pub mod did;
+pub mod error;
+pub mod timestamp;
 pub mod transfer;
+pub mod validate;
 pub mod wallet;
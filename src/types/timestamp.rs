@@ -0,0 +1,262 @@
+//! Timestamp types
+//!
+//! The platform exchanges all times as millisecond Unix timestamps. [`Timestamp`] wraps such a
+//! value, serialized as the same integer on the wire, and converts to and from [`SystemTime`].
+//! With the `chrono` or `time` feature enabled it also converts to and from
+//! `chrono::DateTime<Utc>` and `time::OffsetDateTime`.
+//!
+//! [`TimeRange`] builds the begin/end pairs used by order queries. These are sent as millisecond
+//! timestamps like every other time. The original SDK described them as `yyyy-MM-dd` while
+//! typing them as integers, and the API reference does not specify their format, so confirm
+//! the expected precision with the platform before relying on exact range boundaries.
+//!
+//! JWT claims such as `exp` are in seconds; fields carrying them use [`secs`] to convert.
+//!
+//! # Example
+//!
+//! ```
+//! use onechain_wallet_rust_sdk::types::timestamp::TimeRange;
+//! use onechain_wallet_rust_sdk::types::transfer::TransferOrderQueryPageReq;
+//! use onechain_wallet_rust_sdk::sui_types::base_types::SuiAddress;
+//!
+//! let req = TransferOrderQueryPageReq::new(SuiAddress::ZERO).create_time(TimeRange::last_days(7));
+//! ```
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Millisecond Unix timestamp
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    /// The Unix epoch
+    pub const UNIX_EPOCH: Timestamp = Timestamp(0);
+
+    pub const fn from_millis(millis: i64) -> Self {
+        Self(millis)
+    }
+
+    pub const fn from_secs(secs: i64) -> Self {
+        Self(secs.saturating_mul(1000))
+    }
+
+    /// Current time
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    pub const fn as_millis(&self) -> i64 {
+        self.0
+    }
+
+    /// Whole seconds since the Unix epoch, rounded towards negative infinity
+    pub const fn as_secs(&self) -> i64 {
+        self.0.div_euclid(1000)
+    }
+
+    pub fn to_system_time(&self) -> SystemTime {
+        let offset = Duration::from_millis(self.0.unsigned_abs());
+        if self.0 >= 0 {
+            UNIX_EPOCH + offset
+        } else {
+            UNIX_EPOCH - offset
+        }
+    }
+
+    /// Adds a duration, saturating at the maximum timestamp
+    pub fn saturating_add(&self, duration: Duration) -> Self {
+        Self(self.0.saturating_add(duration_millis(duration)))
+    }
+
+    /// Subtracts a duration, saturating at the minimum timestamp
+    pub fn saturating_sub(&self, duration: Duration) -> Self {
+        Self(self.0.saturating_sub(duration_millis(duration)))
+    }
+
+    /// Whether this timestamp lies in the past
+    pub fn is_past(&self) -> bool {
+        *self < Self::now()
+    }
+}
+
+fn duration_millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<i64> for Timestamp {
+    fn from(millis: i64) -> Self {
+        Self(millis)
+    }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => Self(duration_millis(since)),
+            Err(before) => Self(duration_millis(before.duration()).saturating_neg()),
+        }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_system_time()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for Timestamp {
+    fn from(time: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(time.timestamp_millis())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp {
+    /// Converts to a UTC date time, `None` if out of the range supported by chrono
+    pub fn to_chrono(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::from_timestamp_millis(self.0)
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for Timestamp {
+    fn from(time: time::OffsetDateTime) -> Self {
+        Self(i64::try_from(time.unix_timestamp_nanos() / 1_000_000).unwrap_or(i64::MAX))
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp {
+    /// Converts to a UTC date time, `None` if out of the range supported by time
+    pub fn to_offset_date_time(&self) -> Option<time::OffsetDateTime> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(i128::from(self.0) * 1_000_000).ok()
+    }
+}
+
+/// Serde adapter for timestamps encoded as whole seconds, as in JWT claims
+///
+/// Use with `#[serde(with = "crate::types::timestamp::secs")]`. Sub-second precision is
+/// truncated when serializing.
+pub mod secs {
+    use super::Timestamp;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        timestamp: &Timestamp,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(timestamp.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        i64::deserialize(deserializer).map(Timestamp::from_secs)
+    }
+}
+
+/// Time range with inclusive begin and end
+///
+/// Order queries send both ends as millisecond timestamps, see the
+/// [module documentation](self) on why this format is unconfirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeRange {
+    pub begin: Timestamp,
+    pub end: Timestamp,
+}
+
+impl TimeRange {
+    pub fn new(begin: impl Into<Timestamp>, end: impl Into<Timestamp>) -> Self {
+        Self {
+            begin: begin.into(),
+            end: end.into(),
+        }
+    }
+
+    /// The period of the given length ending now
+    pub fn last(duration: Duration) -> Self {
+        let end = Timestamp::now();
+        Self {
+            begin: end.saturating_sub(duration),
+            end,
+        }
+    }
+
+    /// The last `days` days, ending now
+    pub fn last_days(days: u32) -> Self {
+        Self::last(Duration::from_secs(u64::from(days) * 24 * 60 * 60))
+    }
+
+    /// The last `hours` hours, ending now
+    pub fn last_hours(hours: u32) -> Self {
+        Self::last(Duration::from_secs(u64::from(hours) * 60 * 60))
+    }
+
+    /// From `begin` until now
+    pub fn since(begin: impl Into<Timestamp>) -> Self {
+        Self::new(begin, Timestamp::now())
+    }
+
+    pub fn contains(&self, timestamp: Timestamp) -> bool {
+        self.begin <= timestamp && timestamp <= self.end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let timestamp = Timestamp::from_millis(1_700_000_000_123);
+        assert_eq!(timestamp.as_secs(), 1_700_000_000);
+        assert_eq!(Timestamp::from(timestamp.to_system_time()), timestamp);
+        assert_eq!(serde_json::to_string(&timestamp).unwrap(), "1700000000123");
+        assert_eq!(
+            serde_json::from_str::<Timestamp>("1700000000123").unwrap(),
+            timestamp
+        );
+
+        #[derive(Serialize, Deserialize)]
+        struct Claims {
+            #[serde(with = "secs")]
+            exp: Timestamp,
+        }
+        let claims: Claims = serde_json::from_str(r#"{"exp":1700000000}"#).unwrap();
+        assert_eq!(claims.exp, Timestamp::from_secs(1_700_000_000));
+        assert_eq!(
+            serde_json::to_string(&claims).unwrap(),
+            r#"{"exp":1700000000}"#
+        );
+
+        let before_epoch = Timestamp::from_millis(-1_500);
+        assert_eq!(before_epoch.as_secs(), -2);
+        assert_eq!(Timestamp::from(before_epoch.to_system_time()), before_epoch);
+    }
+
+    #[test]
+    fn last_days() {
+        let range = TimeRange::last_days(7);
+        assert_eq!(
+            range.end.as_millis() - range.begin.as_millis(),
+            7 * 86_400_000
+        );
+        assert!(range.contains(range.begin.saturating_add(Duration::from_secs(1))));
+        assert!(!range.contains(range.end.saturating_add(Duration::from_millis(1))));
+    }
+}
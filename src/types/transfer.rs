@@ -1,26 +1,112 @@
+use crate::sui_types::base_types::SuiAddress;
+use crate::sui_types::digests::TransactionDigest;
+use crate::types::common::{ExtraFields, empty_as_none, none_as_empty};
+use crate::types::timestamp::{TimeRange, Timestamp};
 use serde::{Deserialize, Serialize};
 
+/// Transfer order query
+///
+/// # Example
+///
+/// ```
+/// use onechain_wallet_rust_sdk::sui_types::digests::TransactionDigest;
+/// use onechain_wallet_rust_sdk::types::transfer::TransferOrderQueryReq;
+///
+/// fn successful_order(hash: TransactionDigest) -> TransferOrderQueryReq {
+///     TransferOrderQueryReq::new(hash)
+///         .currency("OCT")
+///         .status_list(vec!["SUCCESS".to_string()])
+/// }
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferOrderQueryReq {
     /// Transaction hash
-    pub hash: String,
-    /// Recipient address
-    pub to_address: String,
+    pub hash: TransactionDigest,
+    /// Recipient address, sent as an empty string if unset
+    #[serde(
+        default,
+        serialize_with = "none_as_empty",
+        deserialize_with = "empty_as_none"
+    )]
+    pub to_address: Option<SuiAddress>,
     /// Transfer currency
     pub currency: String,
     /// Status list
     pub status_list: Option<Vec<String>>,
-    /// Start time yyyy-MM-dd
-    pub begin_time: Option<i64>,
-    /// End time yyyy-MM-dd
-    pub end_time: Option<i64>,
-    /// Completion start time yyyy-MM-dd
-    pub complete_begin_time: Option<i64>,
-    /// Completion end time yyyy-MM-dd
-    pub complete_end_time: Option<i64>,
+    /// Start time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub begin_time: Option<Timestamp>,
+    /// End time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub end_time: Option<Timestamp>,
+    /// Completion start time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub complete_begin_time: Option<Timestamp>,
+    /// Completion end time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub complete_end_time: Option<Timestamp>,
 }
 
+impl TransferOrderQueryReq {
+    /// Creates a query for the order with the given transaction hash
+    pub fn new(hash: TransactionDigest) -> Self {
+        Self {
+            hash,
+            to_address: None,
+            currency: String::new(),
+            status_list: None,
+            begin_time: None,
+            end_time: None,
+            complete_begin_time: None,
+            complete_end_time: None,
+        }
+    }
+
+    pub fn to_address(mut self, to_address: SuiAddress) -> Self {
+        self.to_address = Some(to_address);
+        self
+    }
+
+    pub fn currency(mut self, currency: impl Into<String>) -> Self {
+        self.currency = currency.into();
+        self
+    }
+
+    pub fn status_list(mut self, status_list: Vec<String>) -> Self {
+        self.status_list = Some(status_list);
+        self
+    }
+
+    /// Sets the creation time range
+    pub fn create_time(mut self, range: TimeRange) -> Self {
+        self.begin_time = Some(range.begin);
+        self.end_time = Some(range.end);
+        self
+    }
+
+    /// Sets the completion time range
+    pub fn complete_time(mut self, range: TimeRange) -> Self {
+        self.complete_begin_time = Some(range.begin);
+        self.complete_end_time = Some(range.end);
+        self
+    }
+}
+
+/// Paged transfer order query
+///
+/// [`TransferOrderQueryPageReq::new`] starts at the first page with [`DEFAULT_PAGE_SIZE`] rows.
+///
+/// # Example
+///
+/// ```
+/// use onechain_wallet_rust_sdk::sui_types::base_types::SuiAddress;
+/// use onechain_wallet_rust_sdk::types::transfer::TransferOrderQueryPageReq;
+///
+/// let address = "0x643e921c885d47795e3753803193f37ff70c44a412be9c046de51263f39ba0b5"
+///     .parse::<SuiAddress>()
+///     .unwrap();
+/// let req = TransferOrderQueryPageReq::new(address)
+///     .currency("OCT")
+///     .query_type("1")
+///     .page(2, 20);
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferOrderQueryPageReq {
@@ -28,15 +114,15 @@ pub struct TransferOrderQueryPageReq {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub did: Option<String>,
     /// User address
-    pub address: String,
+    pub address: SuiAddress,
     /// Order ID
     pub order_id: Option<String>,
     /// Transaction hash
-    pub trade_hash: Option<String>,
+    pub trade_hash: Option<TransactionDigest>,
     /// Recipient user DID
     pub to_did: Option<String>,
     /// Recipient address
-    pub to_address: Option<String>,
+    pub to_address: Option<SuiAddress>,
     /// Minimum amount
     pub min_amount: Option<String>,
     /// Maximum amount
@@ -47,27 +133,139 @@ pub struct TransferOrderQueryPageReq {
     pub transfer_method: Option<String>,
     /// Status list
     pub status_list: Option<Vec<String>>,
-    /// Start time yyyy-MM-dd
-    pub begin_time: Option<i64>,
-    /// End time yyyy-MM-dd
-    pub end_time: Option<i64>,
+    /// Start time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub begin_time: Option<Timestamp>,
+    /// End time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub end_time: Option<Timestamp>,
     /// Query direction 0: in 1: out 2: in or out 3: both in and out
     pub query_type: Option<String>,
-    /// Completion start time yyyy-MM-dd
-    pub complete_begin_time: Option<i64>,
-    /// Completion end time yyyy-MM-dd
-    pub complete_end_time: Option<i64>,
+    /// Completion start time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub complete_begin_time: Option<Timestamp>,
+    /// Completion end time in milliseconds, see [`TimeRange`] on the unconfirmed format
+    pub complete_end_time: Option<Timestamp>,
+    /// Page index, starting from 1
     pub page_index: i64,
+    /// Number of rows per page
     pub page_size: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Page size used by [`TransferOrderQueryPageReq::new`]
+pub const DEFAULT_PAGE_SIZE: i64 = 10;
+
+impl TransferOrderQueryPageReq {
+    /// Creates a query for the orders of the given address
+    pub fn new(address: SuiAddress) -> Self {
+        Self {
+            did: None,
+            address,
+            order_id: None,
+            trade_hash: None,
+            to_did: None,
+            to_address: None,
+            min_amount: None,
+            max_amount: None,
+            currency: None,
+            transfer_method: None,
+            status_list: None,
+            begin_time: None,
+            end_time: None,
+            query_type: None,
+            complete_begin_time: None,
+            complete_end_time: None,
+            page_index: 1,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn did(mut self, did: impl Into<String>) -> Self {
+        self.did = Some(did.into());
+        self
+    }
+
+    pub fn order_id(mut self, order_id: impl Into<String>) -> Self {
+        self.order_id = Some(order_id.into());
+        self
+    }
+
+    pub fn trade_hash(mut self, trade_hash: TransactionDigest) -> Self {
+        self.trade_hash = Some(trade_hash);
+        self
+    }
+
+    pub fn to_did(mut self, to_did: impl Into<String>) -> Self {
+        self.to_did = Some(to_did.into());
+        self
+    }
+
+    pub fn to_address(mut self, to_address: SuiAddress) -> Self {
+        self.to_address = Some(to_address);
+        self
+    }
+
+    /// Sets the amount range, either bound may be omitted
+    pub fn amount_range(mut self, min_amount: Option<String>, max_amount: Option<String>) -> Self {
+        self.min_amount = min_amount;
+        self.max_amount = max_amount;
+        self
+    }
+
+    pub fn currency(mut self, currency: impl Into<String>) -> Self {
+        self.currency = Some(currency.into());
+        self
+    }
+
+    /// Transfer method: DID,ADDRESS,NAME
+    pub fn transfer_method(mut self, transfer_method: impl Into<String>) -> Self {
+        self.transfer_method = Some(transfer_method.into());
+        self
+    }
+
+    pub fn status_list(mut self, status_list: Vec<String>) -> Self {
+        self.status_list = Some(status_list);
+        self
+    }
+
+    /// Sets the creation time range
+    pub fn create_time(mut self, range: TimeRange) -> Self {
+        self.begin_time = Some(range.begin);
+        self.end_time = Some(range.end);
+        self
+    }
+
+    /// Sets the completion time range
+    pub fn complete_time(mut self, range: TimeRange) -> Self {
+        self.complete_begin_time = Some(range.begin);
+        self.complete_end_time = Some(range.end);
+        self
+    }
+
+    /// Query direction 0: in 1: out 2: in or out 3: both in and out
+    pub fn query_type(mut self, query_type: impl Into<String>) -> Self {
+        self.query_type = Some(query_type.into());
+        self
+    }
+
+    /// Sets the page index, starting from 1, and the page size
+    pub fn page(mut self, page_index: i64, page_size: i64) -> Self {
+        self.page_index = page_index;
+        self.page_size = page_size;
+        self
+    }
+
+    /// Advances to the next page
+    pub fn next_page(mut self) -> Self {
+        self.page_index += 1;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferOrderReq {
     /// Sender address
-    pub from_address: String,
+    pub from_address: SuiAddress,
     /// Recipient address
-    pub to_address: String,
+    pub to_address: SuiAddress,
     /// Transfer coinType
     pub coin_type: String,
     /// Transfer amount
@@ -76,11 +274,11 @@ pub struct TransferOrderReq {
     pub remark: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TransferOrderTxReq {
     /// Transaction hash
-    pub hash: String,
+    pub hash: TransactionDigest,
     /// Raw transaction content
     pub tx_bytes: String,
     /// User signature
@@ -91,48 +289,53 @@ pub struct TransferOrderTxReq {
 #[serde(rename_all = "camelCase")]
 pub struct TransferOrderResp {
     /// Transaction hash
-    pub hash: String,
+    pub hash: TransactionDigest,
     /// Sender user DID
     pub did: Option<String>,
     /// Sender user nickname
     pub nick_name: Option<String>,
-    /// Sender account
-    pub address: String,
+    /// Sender account, `None` if the server sent no address
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub address: Option<SuiAddress>,
     /// Sender HCname
     pub address_name: Option<String>,
     /// Sender application
-    pub merchant_id: String,
-    pub merchant_name: String,
+    pub merchant_id: Option<String>,
+    pub merchant_name: Option<String>,
     /// Transfer type
-    pub transfer_method: String,
+    pub transfer_method: Option<String>,
     /// Recipient user DID
     pub to_did: Option<String>,
     /// Recipient user nickname
     pub to_nick_name: Option<String>,
-    /// Recipient account
-    pub to_address: String,
+    /// Recipient account, `None` if the server sent no address
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub to_address: Option<SuiAddress>,
     /// Recipient HCname
     pub to_address_name: Option<String>,
     /// Merchant ID of the recipient address
-    pub to_merchant_id: String,
+    pub to_merchant_id: Option<String>,
     /// Merchant name of the recipient address
-    pub to_merchant_name: String,
+    pub to_merchant_name: Option<String>,
     /// Currency
-    pub currency: String,
+    pub currency: Option<String>,
     /// Amount
     pub amount: String,
     /// Status
     pub status: String,
     /// Initiation time
-    pub create_time: i64,
-    /// Completion time
-    pub complete_time: i64,
+    pub create_time: Timestamp,
+    /// Completion time, absent until the order completes
+    pub complete_time: Option<Timestamp>,
     /// Remark
-    pub remark: String,
+    pub remark: Option<String>,
     /// Sender name -> displayed according to transfer method
-    pub sender: String,
+    pub sender: Option<String>,
     /// Recipient name -> displayed according to transfer method
-    pub receiver: String,
+    pub receiver: Option<String>,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,14 +344,17 @@ pub struct TransferOrderTxResponse {
     /// Order status UN_PAY:pending payment, RUNNING:transferring, SUCCESS:transfer successful, FAIL:transfer failed, CANCEL:cancelled, TIMEOUT:timeout
     pub status: String,
     /// Transaction hash
-    pub hash: String,
+    pub hash: TransactionDigest,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BuildSponsorTxReq {
     /// Sender address
-    pub address: String,
+    pub address: SuiAddress,
     /// Unsigned transaction (base64)
     pub raw_transaction: String,
     /// Whether to build offline
@@ -161,18 +367,21 @@ pub struct BuildSponsorTxReq {
 #[serde(rename_all = "camelCase")]
 pub struct GasTxBuilderResponse {
     // Hash
-    pub hash: String,
+    pub hash: TransactionDigest,
     /// Transaction to be signed
     pub raw_transaction: String,
     /// Transaction expiration time
-    pub expiration: i64,
+    pub expiration: Timestamp,
     /// Fee sponsorship address
-    pub sponsor: String,
+    pub sponsor: SuiAddress,
     /// Reservation ID
     pub reservation_id: String,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProxyPayTxReq {
     /// User signature
@@ -186,6 +395,67 @@ pub struct ProxyPayTxReq {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProxyPayTxResp {
-    pub hash: String,
+    pub hash: TransactionDigest,
     pub status: bool,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn page_req_builder() {
+        let req = TransferOrderQueryPageReq::new(SuiAddress::ZERO)
+            .currency("OCT")
+            .next_page();
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["address"], SuiAddress::ZERO.to_string());
+        assert_eq!(json["currency"], "OCT");
+        assert_eq!(json["pageIndex"], 2);
+        assert_eq!(json["pageSize"], DEFAULT_PAGE_SIZE);
+        assert!(json["did"].is_null());
+    }
+
+    #[test]
+    fn order_query_sends_empty_to_address() {
+        let req = TransferOrderQueryReq::new(TransactionDigest::ZERO);
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["hash"], TransactionDigest::ZERO.to_string());
+        assert_eq!(json["toAddress"], "");
+        let back: TransferOrderQueryReq = serde_json::from_value(json).unwrap();
+        assert_eq!(back.to_address, None);
+
+        let req = req.to_address(SuiAddress::ZERO);
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["toAddress"], SuiAddress::ZERO.to_string());
+        let back: TransferOrderQueryReq = serde_json::from_value(json).unwrap();
+        assert_eq!(back.to_address, Some(SuiAddress::ZERO));
+    }
+
+    #[test]
+    fn order_resp_tolerates_empty_addresses() {
+        let json = serde_json::json!({
+            "hash": TransactionDigest::ZERO.to_string(),
+            "address": "",
+            "amount": "1",
+            "status": "SUCCESS",
+            "createTime": 1700000000000u64,
+        });
+        let resp: TransferOrderResp = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(resp.address, None);
+        assert_eq!(resp.to_address, None);
+        assert_eq!(resp.merchant_id, None);
+        assert_eq!(resp.currency, None);
+
+        let mut json = json;
+        json["toAddress"] = SuiAddress::ZERO.to_string().into();
+        let resp: TransferOrderResp = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(resp.to_address, Some(SuiAddress::ZERO));
+
+        json["toAddress"] = "0xinvalid".into();
+        assert!(serde_json::from_value::<TransferOrderResp>(json).is_err());
+    }
 }
@@ -0,0 +1,341 @@
+//! Client-side request validation
+//!
+//! Every request type implements [`Validate`], which checks the fields documented as required
+//! before any signature is computed or network round-trip is made. Addresses and transaction
+//! digests are typed as [`SuiAddress`] and
+//! [`TransactionDigest`](crate::sui_types::digests::TransactionDigest), so their syntax is
+//! already checked when they are constructed; required ones are additionally checked not to be
+//! the zero value left by `Default`. The API traits in
+//! [`rpc`](crate::rpc) validate requests automatically; implementations of
+//! [`Call`](crate::rpc::Call) can opt out through [`Call::validate_requests`](crate::rpc::Call::validate_requests).
+//!
+//! Failures are reported as [`ValidationError`], which can be recovered from the returned
+//! `anyhow::Error` with `downcast_ref`.
+use crate::sui_types::base_types::SuiAddress;
+use crate::sui_types::digests::TransactionDigest;
+use crate::types::did::*;
+use crate::types::transfer::*;
+use crate::types::wallet::QueryWalletReq;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Request validation interface
+pub trait Validate {
+    /// Checks the request, returning a [`ValidationError`] for the first invalid field
+    fn validate(&self) -> anyhow::Result<()>;
+}
+
+/// Invalid request field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Field name as sent on the wire
+    pub field: &'static str,
+    /// Reason the value was rejected
+    pub reason: String,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid request field {}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+fn invalid(field: &'static str, reason: impl Into<String>) -> anyhow::Result<()> {
+    Err(ValidationError {
+        field,
+        reason: reason.into(),
+    }
+    .into())
+}
+
+/// Checks that a required field is not empty
+pub fn require_non_empty(field: &'static str, value: &str) -> anyhow::Result<()> {
+    if value.trim().is_empty() {
+        return invalid(field, "cannot be empty");
+    }
+    Ok(())
+}
+
+/// Checks an E.164 country calling code, with or without a leading `+`
+pub fn validate_mobile_prefix(field: &'static str, value: &str) -> anyhow::Result<()> {
+    let digits = value.strip_prefix('+').unwrap_or(value);
+    if digits.is_empty() || digits.len() > 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return invalid(field, "must be a country calling code of 1 to 4 digits");
+    }
+    Ok(())
+}
+
+/// Checks a national mobile number, which must contain 4 to 14 digits
+pub fn validate_mobile(field: &'static str, value: &str) -> anyhow::Result<()> {
+    if !(4..=14).contains(&value.len()) || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return invalid(field, "must be a mobile number of 4 to 14 digits");
+    }
+    Ok(())
+}
+
+/// Checks the syntax of a Sui address
+pub fn validate_address(field: &'static str, value: &str) -> anyhow::Result<()> {
+    if SuiAddress::from_str(value).is_err() {
+        return invalid(field, "must be a 0x-prefixed 32-byte hex address");
+    }
+    Ok(())
+}
+
+/// Checks that a typed address is set, i.e. not the zero address left by `Default`
+pub fn require_address(field: &'static str, value: &SuiAddress) -> anyhow::Result<()> {
+    if *value == SuiAddress::ZERO {
+        return invalid(field, "cannot be the zero address");
+    }
+    Ok(())
+}
+
+/// Checks that a typed transaction digest is set, i.e. not the zero digest left by `Default`
+pub fn require_digest(field: &'static str, value: &TransactionDigest) -> anyhow::Result<()> {
+    if *value == TransactionDigest::ZERO {
+        return invalid(field, "cannot be the zero digest");
+    }
+    Ok(())
+}
+
+/// Checks that a field is non-empty standard base64
+pub fn validate_base64(field: &'static str, value: &str) -> anyhow::Result<()> {
+    require_non_empty(field, value)?;
+    if BASE64_STANDARD.decode(value).is_err() {
+        return invalid(field, "must be valid base64");
+    }
+    Ok(())
+}
+
+/// Checks a positive decimal amount such as `1`, `0.001` or `12.50`
+pub fn validate_amount(field: &'static str, value: &str) -> anyhow::Result<()> {
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() || !all_digits(int) || !all_digits(frac) || value.ends_with('.') {
+        return invalid(field, "must be a decimal number");
+    }
+    if int.bytes().chain(frac.bytes()).all(|b| b == b'0') {
+        return invalid(field, "must be greater than zero");
+    }
+    Ok(())
+}
+
+/// Checks a non-negative decimal integer, as used for big integers encoded as strings
+pub fn validate_decimal_integer(field: &'static str, value: &str) -> anyhow::Result<()> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return invalid(field, "must be a decimal integer");
+    }
+    Ok(())
+}
+
+/// Checks the `header.payload.signature` structure of a JWT
+pub fn validate_jwt(field: &'static str, value: &str) -> anyhow::Result<()> {
+    let parts = value.split('.').collect::<Vec<_>>();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty()) {
+        return invalid(field, "must be a JWT with header, payload and signature");
+    }
+    Ok(())
+}
+
+impl Validate for AuthorizeTokenProfileReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_non_empty("code", &self.code)?;
+        require_non_empty("nonce", &self.nonce)
+    }
+}
+
+impl Validate for SmsAuthenticateReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_mobile_prefix("mobilePrefix", &self.mobile_prefix)?;
+        validate_mobile("mobile", &self.mobile)?;
+        require_non_empty("code", &self.code)?;
+        require_non_empty("smsCode", self.sms_code.expose_secret())
+    }
+}
+
+impl Validate for SmsCodeSendReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_mobile_prefix("mobilePrefix", &self.mobile_prefix)?;
+        validate_mobile("mobile", &self.mobile)
+    }
+}
+
+impl Validate for ZkProofsReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.max_epoch <= 0 {
+            return invalid("maxEpoch", "must be greater than zero");
+        }
+        validate_decimal_integer("jwtRandomness", self.jwt_randomness.expose_secret())?;
+        validate_decimal_integer(
+            "extendedEphemeralPublicKey",
+            &self.extended_ephemeral_public_key,
+        )?;
+        validate_jwt("jwt", self.jwt.expose_secret())?;
+        require_non_empty("salt", self.salt.expose_secret())?;
+        require_non_empty("keyClaimName", &self.key_claim_name)
+    }
+}
+
+impl Validate for RefreshJwtTokenReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_non_empty("nonce", &self.nonce)
+    }
+}
+
+impl Validate for TransferOrderQueryReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_digest("hash", &self.hash)?;
+        if let Some(to_address) = &self.to_address {
+            require_address("toAddress", to_address)?;
+        }
+        Ok(())
+    }
+}
+
+impl Validate for TransferOrderQueryPageReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_address("address", &self.address)?;
+        if let Some(to_address) = &self.to_address {
+            require_address("toAddress", to_address)?;
+        }
+        if let Some(min_amount) = &self.min_amount {
+            validate_amount("minAmount", min_amount)?;
+        }
+        if let Some(max_amount) = &self.max_amount {
+            validate_amount("maxAmount", max_amount)?;
+        }
+        if self.page_index < 1 {
+            return invalid("pageIndex", "must be at least 1");
+        }
+        if self.page_size < 1 {
+            return invalid("pageSize", "must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+impl Validate for TransferOrderReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_address("fromAddress", &self.from_address)?;
+        require_address("toAddress", &self.to_address)?;
+        require_non_empty("coinType", &self.coin_type)?;
+        validate_amount("amount", &self.amount)
+    }
+}
+
+impl Validate for TransferOrderTxReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_digest("hash", &self.hash)?;
+        validate_base64("txBytes", &self.tx_bytes)?;
+        validate_base64("userSig", &self.user_sig)
+    }
+}
+
+impl Validate for BuildSponsorTxReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        require_address("address", &self.address)?;
+        validate_base64("rawTransaction", &self.raw_transaction)?;
+        if let Some(gas_budget) = &self.gas_budget {
+            validate_amount("gasBudget", gas_budget)?;
+        }
+        Ok(())
+    }
+}
+
+impl Validate for ProxyPayTxReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_base64("userSig", &self.user_sig)?;
+        validate_base64("txBytes", &self.tx_bytes)?;
+        require_non_empty("reservationId", &self.reservation_id)
+    }
+}
+
+impl Validate for QueryWalletReq {
+    fn validate(&self) -> anyhow::Result<()> {
+        let has_did = self.did.as_deref().is_some_and(|did| !did.is_empty());
+        match &self.address {
+            Some(address) => require_address("address", address),
+            None if has_did => Ok(()),
+            None => invalid("address", "either did or address is required"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn amount() {
+        for ok in ["1", "0.001", "12.50", "100"] {
+            validate_amount("amount", ok).unwrap();
+        }
+        for err in ["", "0", "0.000", ".5", "1.", "-1", "1e3", "1.2.3", " 1"] {
+            assert!(validate_amount("amount", err).is_err(), "{}", err);
+        }
+    }
+
+    #[test]
+    fn mobile() {
+        validate_mobile_prefix("mobilePrefix", "855").unwrap();
+        validate_mobile_prefix("mobilePrefix", "+86").unwrap();
+        assert!(validate_mobile_prefix("mobilePrefix", "+").is_err());
+        assert!(validate_mobile_prefix("mobilePrefix", "85a").is_err());
+
+        validate_mobile("mobile", "123123123").unwrap();
+        assert!(validate_mobile("mobile", "123").is_err());
+        assert!(validate_mobile("mobile", "123-123-123").is_err());
+    }
+
+    #[test]
+    fn validation_error() {
+        let req = SmsCodeSendReq {
+            mobile: "".to_string(),
+            mobile_prefix: "855".to_string(),
+            provider: Provider::Huione,
+        };
+        let err = req.validate().unwrap_err();
+        let err = err.downcast_ref::<ValidationError>().unwrap();
+        assert_eq!(err.field, "mobile");
+    }
+
+    #[test]
+    fn zero_address_and_digest() {
+        let err = TransferOrderQueryReq::new(TransactionDigest::ZERO)
+            .validate()
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<ValidationError>().unwrap().field, "hash");
+        TransferOrderQueryReq::new(TransactionDigest::random())
+            .validate()
+            .unwrap();
+        let err = TransferOrderQueryReq::new(TransactionDigest::random())
+            .to_address(SuiAddress::ZERO)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ValidationError>().unwrap().field,
+            "toAddress"
+        );
+
+        let err = TransferOrderQueryPageReq::new(SuiAddress::ZERO)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ValidationError>().unwrap().field,
+            "address"
+        );
+        TransferOrderQueryPageReq::new(SuiAddress::random_for_testing_only())
+            .validate()
+            .unwrap();
+
+        let err = QueryWalletReq::by_address(SuiAddress::ZERO)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ValidationError>().unwrap().field,
+            "address"
+        );
+    }
+}
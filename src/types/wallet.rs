@@ -1,3 +1,6 @@
+use crate::sui_types::base_types::SuiAddress;
+use crate::types::common::{ExtraFields, empty_as_none};
+use crate::types::timestamp::Timestamp;
 use serde::{Deserialize, Serialize};
 
 /// Chain currency information
@@ -8,7 +11,11 @@ pub struct CurrencyChainResp {
     pub chain: String,
 
     /// Currency list
+    #[serde(default)]
     pub currency_list: Vec<CurrencyInfo>,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
 /// Currency information
@@ -22,13 +29,13 @@ pub struct CurrencyInfo {
     pub currency: String,
 
     /// Currency name
-    pub name: String,
+    pub name: Option<String>,
 
     /// Currency icon
     pub pic: Option<String>,
 
     /// USD exchange rate
-    pub exchange_rate: String,
+    pub exchange_rate: Option<String>,
 
     /// Display precision
     pub display_decimals: i32,
@@ -37,24 +44,46 @@ pub struct CurrencyInfo {
     pub calculate_decimals: i32,
 
     /// Creation time
-    pub create_time: i64,
+    pub create_time: Option<Timestamp>,
 
     /// Update time
-    pub update_time: i64,
+    pub update_time: Option<Timestamp>,
 
     /// Currency symbol
-    pub symbol: String,
+    pub symbol: Option<String>,
 
     /// Currency collection address
     pub coin_type: String,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct QueryWalletReq {
     /// User did, pass either this or wallet address
     pub did: Option<String>,
     /// User wallet address, pass either this or did
-    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<SuiAddress>,
+}
+
+impl QueryWalletReq {
+    /// Queries the wallets of a user DID
+    pub fn by_did(did: impl Into<String>) -> Self {
+        Self {
+            did: Some(did.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Queries the wallets of an address
+    pub fn by_address(address: SuiAddress) -> Self {
+        Self {
+            address: Some(address),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,17 +91,21 @@ pub struct UserWalletResp {
     ///  DID
     pub did: Option<String>,
     /// Login user number
-    pub user_no: String,
-    /// Address
-    pub address: String,
+    pub user_no: Option<String>,
+    /// Address, `None` if the server sent no address
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub address: Option<SuiAddress>,
     /// Chain
-    pub chain: String,
+    pub chain: Option<String>,
     /// Account number
-    pub account: String,
+    pub account: Option<String>,
     /// Account name
-    pub account_name: String,
+    pub account_name: Option<String>,
     /// Wallet type
-    pub wallet_type: String,
+    pub wallet_type: Option<String>,
     /// Alias
     pub alias_name: Option<String>,
+    /// Fields not known to this SDK version
+    #[serde(flatten)]
+    pub extra: ExtraFields,
 }
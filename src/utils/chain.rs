@@ -0,0 +1,395 @@
+//! Chain state needed by zkLogin sessions
+//!
+//! A zkLogin proof is bound to a `max_epoch`: once the chain has moved past it, signatures made
+//! with the ephemeral key are rejected and the user has to log in again. [`ChainInfo`] exposes
+//! the little chain state needed to choose that epoch, without pulling in a full node SDK:
+//!
+//! * [`JsonRpcChainInfo`] queries a full node over JSON-RPC with the SDK's HTTP client
+//! * [`StaticChainInfo`] returns a fixed snapshot, for tests and offline use
+//!
+//! [`select_max_epoch`] picks the smallest `max_epoch` covering a desired session lifetime, and
+//! [`EpochInfo::expiration`] tells when a proof for a given `max_epoch` stops being accepted.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::utils::chain::{JsonRpcChainInfo, select_max_epoch};
+//! use std::time::Duration;
+//!
+//! async fn example() -> anyhow::Result<()> {
+//!     let chain = JsonRpcChainInfo::new("https://rpc-devnet.onelabs.cc:443", None)?;
+//!     let max_epoch = select_max_epoch(&chain, Duration::from_secs(7 * 24 * 3600)).await?;
+//!     println!("max epoch {}", max_epoch);
+//!     Ok(())
+//! }
+//! ```
+use crate::client::get_rest_client;
+use crate::types::timestamp::Timestamp;
+use async_trait::async_trait;
+use reqwest::Url;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum number of epochs a zkLogin `max_epoch` may lie beyond the current epoch
+pub const MAX_EPOCH_DELTA: u64 = 30;
+
+/// Snapshot of the current epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochInfo {
+    /// Current epoch
+    #[serde(deserialize_with = "de_u64")]
+    pub epoch: u64,
+    /// Start time of the current epoch
+    #[serde(rename = "epochStartTimestampMs", deserialize_with = "de_timestamp")]
+    pub epoch_start: Timestamp,
+    /// Configured epoch duration
+    #[serde(
+        rename = "epochDurationMs",
+        serialize_with = "ser_duration",
+        deserialize_with = "de_duration"
+    )]
+    pub epoch_duration: Duration,
+    /// Reference gas price of the current epoch, in MIST
+    #[serde(deserialize_with = "de_u64")]
+    pub reference_gas_price: u64,
+}
+
+impl EpochInfo {
+    /// Expected end of the current epoch
+    pub fn epoch_end(&self) -> Timestamp {
+        self.epoch_start.saturating_add(self.epoch_duration)
+    }
+
+    /// Expected time after which proofs bound to `max_epoch` are rejected
+    ///
+    /// Epoch changes are not exactly on schedule, treat the result as an estimate.
+    pub fn expiration(&self, max_epoch: u64) -> Timestamp {
+        let epochs = max_epoch.saturating_sub(self.epoch).saturating_add(1);
+        let duration = self
+            .epoch_duration
+            .checked_mul(u32::try_from(epochs).unwrap_or(u32::MAX))
+            .unwrap_or(Duration::MAX);
+        self.epoch_start.saturating_add(duration)
+    }
+
+    /// Smallest `max_epoch` whose proof stays valid for `lifetime` from now
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the lifetime needs more than [`MAX_EPOCH_DELTA`] epochs or the
+    /// epoch duration is zero
+    pub fn max_epoch_for(&self, lifetime: Duration) -> anyhow::Result<u64> {
+        self.max_epoch_at(Timestamp::now(), lifetime)
+    }
+
+    /// Same as [`EpochInfo::max_epoch_for`], measuring the lifetime from `now`
+    pub fn max_epoch_at(&self, now: Timestamp, lifetime: Duration) -> anyhow::Result<u64> {
+        anyhow::ensure!(!self.epoch_duration.is_zero(), "epoch duration is zero");
+        let session_end = now.saturating_add(lifetime).as_millis();
+        let epoch_end = self.epoch_end().as_millis();
+        let extra = if session_end <= epoch_end {
+            0
+        } else {
+            let epoch_ms = self.epoch_duration.as_millis() as u64;
+            ((session_end - epoch_end) as u64).div_ceil(epoch_ms)
+        };
+        anyhow::ensure!(
+            extra <= MAX_EPOCH_DELTA,
+            "session lifetime of {}s needs {} epochs, at most {} are allowed",
+            lifetime.as_secs(),
+            extra,
+            MAX_EPOCH_DELTA
+        );
+        Ok(self.epoch + extra)
+    }
+}
+
+/// Source of the chain state used to choose `max_epoch`
+#[async_trait]
+pub trait ChainInfo: Send + Sync {
+    /// Fetches the current epoch
+    async fn epoch_info(&self) -> anyhow::Result<EpochInfo>;
+
+    /// Current epoch
+    async fn current_epoch(&self) -> anyhow::Result<u64> {
+        Ok(self.epoch_info().await?.epoch)
+    }
+
+    /// Configured epoch duration
+    async fn epoch_duration(&self) -> anyhow::Result<Duration> {
+        Ok(self.epoch_info().await?.epoch_duration)
+    }
+
+    /// Reference gas price of the current epoch, in MIST
+    async fn reference_gas_price(&self) -> anyhow::Result<u64> {
+        Ok(self.epoch_info().await?.reference_gas_price)
+    }
+}
+
+/// Picks the smallest `max_epoch` whose proof stays valid for `lifetime` from now
+///
+/// # Parameters
+///
+/// * `chain` - Source of the current epoch
+/// * `lifetime` - Desired session lifetime
+pub async fn select_max_epoch<C: ChainInfo + ?Sized>(
+    chain: &C,
+    lifetime: Duration,
+) -> anyhow::Result<u64> {
+    chain.epoch_info().await?.max_epoch_for(lifetime)
+}
+
+/// [`ChainInfo`] backed by the JSON-RPC interface of a full node
+#[derive(Clone)]
+pub struct JsonRpcChainInfo {
+    url: Url,
+    http_client: Arc<ClientWithMiddleware>,
+}
+
+impl JsonRpcChainInfo {
+    /// Creates a client for the full node at `url`
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - JSON-RPC endpoint, e.g. `https://rpc-devnet.onelabs.cc:443`
+    /// * `client` - HTTP client, the default client of [`get_rest_client`] is used if `None`
+    pub fn new(url: &str, client: Option<Arc<ClientWithMiddleware>>) -> anyhow::Result<Self> {
+        Ok(Self {
+            url: Url::parse(url)?,
+            http_client: client.unwrap_or_else(|| Arc::new(get_rest_client())),
+        })
+    }
+
+    /// Calls a JSON-RPC method and returns its result
+    ///
+    /// Errors reported by the node are returned as [`RpcError`], recoverable with
+    /// `downcast_ref`.
+    pub async fn request<T>(&self, method: &str, params: Value) -> anyhow::Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response = self
+            .http_client
+            .post(self.url.clone())
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        let envelope = response.json::<JsonRpcResponse<T>>().await?;
+        if let Some(error) = envelope.error {
+            return Err(RpcError {
+                method: method.to_string(),
+                code: error.code,
+                message: error.message,
+            }
+            .into());
+        }
+        envelope
+            .result
+            .ok_or_else(|| anyhow::anyhow!("{} returned neither result nor error", method))
+    }
+}
+
+#[async_trait]
+impl ChainInfo for JsonRpcChainInfo {
+    async fn epoch_info(&self) -> anyhow::Result<EpochInfo> {
+        self.request("suix_getLatestSuiSystemState", json!([]))
+            .await
+    }
+
+    async fn reference_gas_price(&self) -> anyhow::Result<u64> {
+        let price: StringOrNumber = self.request("suix_getReferenceGasPrice", json!([])).await?;
+        price.into_u64()
+    }
+}
+
+impl Debug for JsonRpcChainInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonRpcChainInfo")
+            .field("url", &self.url.as_str())
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`ChainInfo`] returning a fixed snapshot
+#[derive(Debug, Clone)]
+pub struct StaticChainInfo(pub EpochInfo);
+
+#[async_trait]
+impl ChainInfo for StaticChainInfo {
+    async fn epoch_info(&self) -> anyhow::Result<EpochInfo> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Error returned by a full node for a JSON-RPC call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    /// Called method
+    pub method: String,
+    /// JSON-RPC error code
+    pub code: i64,
+    /// Error message
+    pub message: String,
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} failed with code {}: {}",
+            self.method, self.code, self.message
+        )
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Full nodes encode 64-bit integers as strings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrNumber {
+    String(String),
+    Number(u64),
+}
+
+impl StringOrNumber {
+    fn into_u64(self) -> anyhow::Result<u64> {
+        match self {
+            StringOrNumber::String(s) => Ok(s.parse()?),
+            StringOrNumber::Number(n) => Ok(n),
+        }
+    }
+}
+
+fn de_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    StringOrNumber::deserialize(deserializer)?
+        .into_u64()
+        .map_err(serde::de::Error::custom)
+}
+
+fn de_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+    let millis = de_u64(deserializer)?;
+    Ok(Timestamp::from_millis(
+        i64::try_from(millis).map_err(serde::de::Error::custom)?,
+    ))
+}
+
+fn de_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    Ok(Duration::from_millis(de_u64(deserializer)?))
+}
+
+fn ser_duration<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::TestServer;
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    fn info() -> EpochInfo {
+        serde_json::from_value(json!({
+            "epoch": "100",
+            "epochStartTimestampMs": "1700000000000",
+            "epochDurationMs": "86400000",
+            "referenceGasPrice": "1000",
+            "protocolVersion": "70",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn max_epoch() {
+        let info = info();
+        assert_eq!(info.epoch, 100);
+        assert_eq!(info.epoch_duration, DAY);
+        assert_eq!(info.reference_gas_price, 1000);
+
+        let now = info.epoch_start.saturating_add(DAY / 2);
+        assert_eq!(info.max_epoch_at(now, DAY / 4).unwrap(), 100);
+        assert_eq!(info.max_epoch_at(now, DAY / 2).unwrap(), 100);
+        assert_eq!(info.max_epoch_at(now, DAY).unwrap(), 101);
+        assert_eq!(info.max_epoch_at(now, DAY * 7).unwrap(), 107);
+        assert!(info.max_epoch_at(now, DAY * 40).is_err());
+
+        let max_epoch = info.max_epoch_at(now, DAY * 3).unwrap();
+        assert!(info.expiration(max_epoch) >= now.saturating_add(DAY * 3));
+    }
+
+    #[tokio::test]
+    async fn json_rpc_chain_info() {
+        let server = TestServer::start(vec![
+            (
+                200,
+                json!({"jsonrpc": "2.0", "id": 1, "result": {
+                    "epoch": "100",
+                    "epochStartTimestampMs": "1700000000000",
+                    "epochDurationMs": "86400000",
+                    "referenceGasPrice": "1000",
+                    "protocolVersion": "70",
+                }}),
+            ),
+            (200, json!({"jsonrpc": "2.0", "id": 1, "result": "750"})),
+            (
+                200,
+                json!({"jsonrpc": "2.0", "id": 1, "error": {
+                    "code": -32602,
+                    "message": "invalid params",
+                }}),
+            ),
+        ])
+        .await;
+        let chain = JsonRpcChainInfo::new(&server.url, None).unwrap();
+
+        assert_eq!(chain.epoch_info().await.unwrap(), info());
+        assert_eq!(chain.reference_gas_price().await.unwrap(), 750);
+        let err = chain.current_epoch().await.unwrap_err();
+        let err = err.downcast_ref::<RpcError>().unwrap();
+        assert_eq!(err.method, "suix_getLatestSuiSystemState");
+        assert_eq!(err.code, -32602);
+        assert_eq!(err.message, "invalid params");
+
+        let methods = server
+            .requests()
+            .iter()
+            .map(|request| request.json()["method"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            methods,
+            [
+                "suix_getLatestSuiSystemState",
+                "suix_getReferenceGasPrice",
+                "suix_getLatestSuiSystemState",
+            ]
+        );
+    }
+}
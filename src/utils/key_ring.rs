@@ -0,0 +1,490 @@
+//! Key ring module for merchant key rotation
+//!
+//! This module allows RSA keys to be rotated without rebuilding the client:
+//!
+//! * [`MerchantKeyRing`] - Holds the active merchant signing key and staged keys. The active key
+//!   can be swapped at runtime through a shared reference, and its id is sent alongside
+//!   `merchantSign` in the [`MERCHANT_KEY_ID`](crate::types::common::MERCHANT_KEY_ID) header.
+//!   The header is opt-in and not documented by the server API: keys registered without an id,
+//!   e.g. through [`MerchantKeyRing::new`], never send it.
+//! * [`PlatformKeyRing`] - Holds several platform verification keys, so signatures made with
+//!   either the old or the new platform key are accepted during a rotation window.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::utils::key_ring::MerchantKeyRing;
+//! use onechain_wallet_rust_sdk::utils::rsa_sign::RSASignUtils;
+//!
+//! let ring = MerchantKeyRing::with_key_id("2024-01", RSASignUtils::new("old_key").unwrap());
+//! // Register the new key ahead of time, then switch once the platform accepts it
+//! ring.insert("2024-07", RSASignUtils::new("new_key").unwrap());
+//! ring.activate("2024-07").unwrap();
+//! ```
+use crate::utils::rsa_sign::{CanonicalMode, RSASignUtils, RSAVerifyUtils};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::SystemTime;
+
+/// Merchant signature together with the id of the key that produced it
+#[derive(Debug, Clone)]
+pub struct MerchantSign {
+    /// Id of the signing key, `None` for a key registered without id
+    pub key_id: Option<String>,
+    /// Base64-encoded signature
+    pub sign: String,
+    /// Link string canonicalization version, `None` for the legacy mode
+    pub version: Option<&'static str>,
+}
+
+/// Merchant signing key ring
+///
+/// Exactly one key is active at a time. Additional keys can be staged with
+/// [`MerchantKeyRing::insert`] and switched to with [`MerchantKeyRing::activate`]; the previously
+/// active key stays staged so a rotation can be rolled back.
+#[derive(Debug)]
+pub struct MerchantKeyRing {
+    state: RwLock<MerchantKeyState>,
+}
+
+#[derive(Debug)]
+struct MerchantKeyState {
+    active_id: Option<String>,
+    active: Arc<RSASignUtils>,
+    staged: HashMap<String, Arc<RSASignUtils>>,
+    /// Canonicalization mode applied to every key of the ring
+    mode: CanonicalMode,
+}
+
+impl MerchantKeyRing {
+    /// Creates a key ring whose active key has no id, no key id header is sent
+    pub fn new(signer: RSASignUtils) -> Self {
+        Self::from_state(None, signer)
+    }
+
+    /// Creates a key ring whose active key is identified by `key_id`
+    pub fn with_key_id(key_id: &str, signer: RSASignUtils) -> Self {
+        Self::from_state(Some(key_id.to_string()), signer)
+    }
+
+    /// The canonicalization mode of the ring is taken from the initial key
+    fn from_state(active_id: Option<String>, signer: RSASignUtils) -> Self {
+        Self {
+            state: RwLock::new(MerchantKeyState {
+                active_id,
+                mode: signer.canonical_mode(),
+                active: Arc::new(signer),
+                staged: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Stages a key without activating it, replacing any staged key with the same id
+    ///
+    /// The key is switched to the canonicalization mode of the ring.
+    pub fn insert(&self, key_id: &str, signer: RSASignUtils) {
+        let mut state = self.write();
+        let signer = signer.with_canonical_mode(state.mode);
+        state.staged.insert(key_id.to_string(), Arc::new(signer));
+    }
+
+    /// Activates a previously staged key
+    ///
+    /// The currently active key is moved to the staged keys if it has an id.
+    pub fn activate(&self, key_id: &str) -> anyhow::Result<()> {
+        let mut state = self.write();
+        if state.active_id.as_deref() == Some(key_id) {
+            return Ok(());
+        }
+        let Some(signer) = state.staged.remove(key_id) else {
+            anyhow::bail!("merchant key {} is not registered", key_id);
+        };
+        let previous_signer = std::mem::replace(&mut state.active, signer);
+        if let Some(previous_id) = state.active_id.replace(key_id.to_string()) {
+            state.staged.insert(previous_id, previous_signer);
+        }
+        Ok(())
+    }
+
+    /// Stages and immediately activates a key
+    ///
+    /// The key is switched to the canonicalization mode of the ring.
+    pub fn rotate(&self, key_id: &str, signer: RSASignUtils) {
+        let mut state = self.write();
+        let signer = signer.with_canonical_mode(state.mode);
+        let previous_signer = std::mem::replace(&mut state.active, Arc::new(signer));
+        if let Some(previous_id) = state.active_id.replace(key_id.to_string())
+            && previous_id != key_id
+        {
+            state.staged.insert(previous_id, previous_signer);
+        }
+        state.staged.remove(key_id);
+    }
+
+    /// Removes a staged key, the active key cannot be removed
+    pub fn remove(&self, key_id: &str) -> anyhow::Result<()> {
+        let mut state = self.write();
+        if state.active_id.as_deref() == Some(key_id) {
+            anyhow::bail!("merchant key {} is active and cannot be removed", key_id);
+        }
+        state.staged.remove(key_id);
+        Ok(())
+    }
+
+    /// Id of the active key
+    pub fn active_key_id(&self) -> Option<String> {
+        self.read().active_id.clone()
+    }
+
+    /// Sets the link string canonicalization mode of the ring
+    ///
+    /// Applies to the active and staged keys as well as keys inserted or rotated in later.
+    pub fn set_canonical_mode(&self, mode: CanonicalMode) {
+        let mut state = self.write();
+        state.mode = mode;
+        state.active = Arc::new(state.active.as_ref().clone().with_canonical_mode(mode));
+        for signer in state.staged.values_mut() {
+            *signer = Arc::new(signer.as_ref().clone().with_canonical_mode(mode));
+        }
+    }
+
+    /// Link string canonicalization mode of the ring
+    pub fn canonical_mode(&self) -> CanonicalMode {
+        self.read().mode
+    }
+
+    /// Ids of the staged keys
+    pub fn staged_key_ids(&self) -> Vec<String> {
+        self.read().staged.keys().cloned().collect()
+    }
+
+    /// RSA sign the data with the active key
+    ///
+    /// # Parameters
+    ///
+    /// * `obj` - the data object to be signed
+    /// * `ignore_fields` - a list of fields to be ignored when signing
+    ///
+    /// # Return value
+    ///
+    /// Returns the Base64-encoded signature and the id of the key used
+    pub fn sign<T: Serialize + Debug>(
+        &self,
+        obj: &T,
+        ignore_fields: Vec<&str>,
+    ) -> anyhow::Result<MerchantSign> {
+        // Release the lock before signing so rotations are never blocked by signing
+        let (key_id, signer) = {
+            let state = self.read();
+            (state.active_id.clone(), state.active.clone())
+        };
+        Ok(MerchantSign {
+            key_id,
+            sign: signer.sign(obj, ignore_fields)?,
+            version: signer.canonical_mode().version(),
+        })
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, MerchantKeyState> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, MerchantKeyState> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Platform verification key ring
+///
+/// Keys may carry an expiry time; expired keys are ignored during verification, which allows
+/// the old platform key to stay valid only for the duration of a rotation window.
+#[derive(Debug, Default)]
+pub struct PlatformKeyRing {
+    keys: RwLock<HashMap<String, PlatformKey>>,
+}
+
+#[derive(Debug, Clone)]
+struct PlatformKey {
+    verifier: Arc<RSAVerifyUtils>,
+    not_after: Option<SystemTime>,
+}
+
+impl PlatformKey {
+    fn is_valid(&self, now: SystemTime) -> bool {
+        self.not_after.is_none_or(|not_after| now <= not_after)
+    }
+}
+
+impl PlatformKeyRing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key that stays valid until it is removed or retired
+    pub fn insert(&self, key_id: &str, verifier: RSAVerifyUtils) {
+        self.insert_key(key_id, verifier, None);
+    }
+
+    /// Adds a key that is valid until `not_after`
+    pub fn insert_until(&self, key_id: &str, verifier: RSAVerifyUtils, not_after: SystemTime) {
+        self.insert_key(key_id, verifier, Some(not_after));
+    }
+
+    fn insert_key(&self, key_id: &str, verifier: RSAVerifyUtils, not_after: Option<SystemTime>) {
+        self.write().insert(
+            key_id.to_string(),
+            PlatformKey {
+                verifier: Arc::new(verifier),
+                not_after,
+            },
+        );
+    }
+
+    /// Keeps a key valid until `not_after`, typically the end of the rotation window
+    pub fn retire(&self, key_id: &str, not_after: SystemTime) -> anyhow::Result<()> {
+        let mut keys = self.write();
+        let Some(key) = keys.get_mut(key_id) else {
+            anyhow::bail!("platform key {} is not registered", key_id);
+        };
+        key.not_after = Some(not_after);
+        Ok(())
+    }
+
+    /// Removes a key immediately
+    pub fn remove(&self, key_id: &str) {
+        self.write().remove(key_id);
+    }
+
+    /// Removes all keys whose validity has ended
+    pub fn prune(&self) {
+        let now = SystemTime::now();
+        self.write().retain(|_, key| key.is_valid(now));
+    }
+
+    /// Ids of the keys that are currently valid
+    pub fn key_ids(&self) -> Vec<String> {
+        let now = SystemTime::now();
+        self.read()
+            .iter()
+            .filter(|(_, key)| key.is_valid(now))
+            .map(|(key_id, _)| key_id.clone())
+            .collect()
+    }
+
+    /// Verify a Base64-encoded platform signature
+    ///
+    /// # Parameters
+    ///
+    /// * `obj` - the signed data object
+    /// * `ignore_fields` - a list of fields to be ignored when building the link string
+    /// * `sign` - the Base64-encoded signature
+    /// * `key_id` - id of the signing key if known, otherwise every valid key is tried
+    ///
+    /// # Return value
+    ///
+    /// Returns the id of the key that verified the signature
+    pub fn verify<T: Serialize + Debug>(
+        &self,
+        obj: &T,
+        ignore_fields: Vec<&str>,
+        sign: &str,
+        key_id: Option<&str>,
+    ) -> anyhow::Result<String> {
+        let now = SystemTime::now();
+        let candidates = self
+            .read()
+            .iter()
+            .filter(|(id, key)| key.is_valid(now) && key_id.is_none_or(|k| k == id.as_str()))
+            .map(|(id, key)| (id.clone(), key.verifier.clone()))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            match key_id {
+                Some(key_id) => anyhow::bail!("platform key {} is not valid", key_id),
+                None => anyhow::bail!("no valid platform key registered"),
+            }
+        }
+        for (id, verifier) in candidates {
+            if verifier.verify(obj, ignore_fields.clone(), sign).is_ok() {
+                return Ok(id);
+            }
+        }
+        anyhow::bail!("invalid platform signature")
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, PlatformKey>> {
+        self.keys.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, PlatformKey>> {
+        self.keys.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MerchantKeyRing, PlatformKeyRing};
+    use crate::testing::{PRI_KEY, PUB_KEY};
+    use crate::types::common::BaseReq;
+    use crate::utils::rsa_sign::{CanonicalMode, RSASignUtils, RSAVerifyUtils};
+    use rsa::RsaPrivateKey;
+    use std::time::{Duration, SystemTime};
+
+    /// Key pair distinct from the shared test key, 1024 bits to keep key generation fast
+    fn key_pair() -> (RSASignUtils, RSAVerifyUtils) {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        (
+            RSASignUtils::from_private_key_allow_weak(key.clone()).unwrap(),
+            RSAVerifyUtils::from_public_key_allow_weak(key.to_public_key()).unwrap(),
+        )
+    }
+
+    /// Request with a fixed timestamp, so separately built requests sign identically
+    fn req() -> BaseReq<String> {
+        BaseReq {
+            timestamp: 1_700_000_000_000,
+            merchant_id: "1000000".to_string(),
+            merchant_sign: String::new(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn merchant_insert_activate() {
+        let (new_signer, new_verifier) = key_pair();
+        let old_verifier = RSAVerifyUtils::new(PUB_KEY).unwrap();
+        let ring = MerchantKeyRing::with_key_id("old", RSASignUtils::new(PRI_KEY).unwrap());
+
+        ring.insert("new", new_signer);
+        assert_eq!(ring.active_key_id().as_deref(), Some("old"));
+        assert_eq!(ring.staged_key_ids(), vec!["new".to_string()]);
+        let signed = ring.sign(&req(), vec![]).unwrap();
+        assert_eq!(signed.key_id.as_deref(), Some("old"));
+        old_verifier.verify(&req(), vec![], &signed.sign).unwrap();
+
+        ring.activate("new").unwrap();
+        assert_eq!(ring.active_key_id().as_deref(), Some("new"));
+        assert_eq!(ring.staged_key_ids(), vec!["old".to_string()]);
+        let signed = ring.sign(&req(), vec![]).unwrap();
+        assert_eq!(signed.key_id.as_deref(), Some("new"));
+        new_verifier.verify(&req(), vec![], &signed.sign).unwrap();
+        assert!(old_verifier.verify(&req(), vec![], &signed.sign).is_err());
+
+        // roll back
+        ring.activate("old").unwrap();
+        assert_eq!(ring.active_key_id().as_deref(), Some("old"));
+        assert!(ring.activate("unknown").is_err());
+    }
+
+    #[test]
+    fn merchant_rotate_remove() {
+        let (new_signer, new_verifier) = key_pair();
+        let ring = MerchantKeyRing::with_key_id("old", RSASignUtils::new(PRI_KEY).unwrap());
+
+        ring.rotate("new", new_signer);
+        assert_eq!(ring.active_key_id().as_deref(), Some("new"));
+        assert_eq!(ring.staged_key_ids(), vec!["old".to_string()]);
+        let signed = ring.sign(&req(), vec![]).unwrap();
+        new_verifier.verify(&req(), vec![], &signed.sign).unwrap();
+
+        assert!(ring.remove("new").is_err());
+        ring.remove("old").unwrap();
+        assert!(ring.staged_key_ids().is_empty());
+        assert!(ring.activate("old").is_err());
+    }
+
+    #[test]
+    fn merchant_mode_applies_to_new_keys() {
+        let (staged_signer, _) = key_pair();
+        let (rotated_signer, rotated_verifier) = key_pair();
+        let ring = MerchantKeyRing::with_key_id("old", RSASignUtils::new(PRI_KEY).unwrap());
+        ring.set_canonical_mode(CanonicalMode::V1);
+        assert_eq!(ring.canonical_mode(), CanonicalMode::V1);
+
+        ring.insert("staged", staged_signer);
+        ring.activate("staged").unwrap();
+        assert_eq!(ring.sign(&req(), vec![]).unwrap().version, Some("1"));
+
+        ring.rotate("rotated", rotated_signer);
+        let signed = ring.sign(&req(), vec![]).unwrap();
+        assert_eq!(signed.version, Some("1"));
+        rotated_verifier
+            .clone()
+            .with_canonical_mode(CanonicalMode::V1)
+            .verify(&req(), vec![], &signed.sign)
+            .unwrap();
+
+        ring.set_canonical_mode(CanonicalMode::Legacy);
+        assert_eq!(ring.sign(&req(), vec![]).unwrap().version, None);
+    }
+
+    #[test]
+    fn merchant_without_key_id() {
+        let (new_signer, _) = key_pair();
+        let ring = MerchantKeyRing::new(RSASignUtils::new(PRI_KEY).unwrap());
+        assert_eq!(ring.sign(&req(), vec![]).unwrap().key_id, None);
+
+        // a key without id is dropped instead of staged when rotated away
+        ring.rotate("new", new_signer);
+        assert!(ring.staged_key_ids().is_empty());
+        assert_eq!(
+            ring.sign(&req(), vec![]).unwrap().key_id.as_deref(),
+            Some("new")
+        );
+    }
+
+    #[test]
+    fn platform_rotation_window() {
+        let (new_signer, new_verifier) = key_pair();
+        let old_signer = RSASignUtils::new(PRI_KEY).unwrap();
+        let ring = PlatformKeyRing::new();
+        ring.insert("old", RSAVerifyUtils::new(PUB_KEY).unwrap());
+        ring.insert("new", new_verifier);
+
+        let old_sign = old_signer.sign(&req(), vec![]).unwrap();
+        let new_sign = new_signer.sign(&req(), vec![]).unwrap();
+        assert_eq!(ring.verify(&req(), vec![], &old_sign, None).unwrap(), "old");
+        assert_eq!(ring.verify(&req(), vec![], &new_sign, None).unwrap(), "new");
+        assert_eq!(
+            ring.verify(&req(), vec![], &new_sign, Some("new")).unwrap(),
+            "new"
+        );
+        assert!(ring.verify(&req(), vec![], &new_sign, Some("old")).is_err());
+
+        // the old key stays valid until the end of the window
+        ring.retire("old", SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(ring.verify(&req(), vec![], &old_sign, None).unwrap(), "old");
+        ring.retire("old", SystemTime::now() - Duration::from_secs(1))
+            .unwrap();
+        assert!(ring.verify(&req(), vec![], &old_sign, None).is_err());
+        assert!(ring.verify(&req(), vec![], &old_sign, Some("old")).is_err());
+        assert_eq!(ring.key_ids(), vec!["new".to_string()]);
+        assert!(ring.retire("unknown", SystemTime::now()).is_err());
+
+        ring.remove("new");
+        assert!(ring.verify(&req(), vec![], &new_sign, None).is_err());
+    }
+
+    #[test]
+    fn platform_insert_until_expiry() {
+        let (signer, verifier) = key_pair();
+        let sign = signer.sign(&req(), vec![]).unwrap();
+        let ring = PlatformKeyRing::new();
+
+        ring.insert_until(
+            "temp",
+            verifier.clone(),
+            SystemTime::now() + Duration::from_secs(60),
+        );
+        assert_eq!(ring.verify(&req(), vec![], &sign, None).unwrap(), "temp");
+
+        ring.insert_until("temp", verifier, SystemTime::now() - Duration::from_secs(1));
+        assert!(ring.key_ids().is_empty());
+        assert!(ring.verify(&req(), vec![], &sign, None).is_err());
+        ring.prune();
+        ring.insert("other", RSAVerifyUtils::new(PUB_KEY).unwrap());
+        assert_eq!(ring.key_ids(), vec!["other".to_string()]);
+    }
+}
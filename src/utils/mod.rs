@@ -1,10 +1,22 @@
 //! Tool module, providing RSA signature and general utility functions.
 //!
-//! This module contains two main parts:
+//! This module contains the following parts:
 //! - RSA signature function: provided by `rsa_sign` submodule
+//! - Merchant and platform key rotation: provided by `key_ring` submodule
+//! - Chain state for choosing zkLogin `max_epoch`: provided by `chain` submodule
+//! - zkLogin proof caching: provided by `proof_cache` submodule
+//! - Multisig wallets mixing zkLogin and classical keys: provided by `multisig` submodule
+//! - Programmable transactions for sponsored calls: provided by `ptb` submodule
+//! - Human-readable transaction previews: provided by `tx_preview` submodule
 //! - General utility functions: such as generating tracking IDs and random strings
 
+pub mod chain;
+pub mod key_ring;
+pub mod multisig;
+pub mod proof_cache;
+pub mod ptb;
 pub mod rsa_sign;
+pub mod tx_preview;
 pub mod zk_login;
 
 use rand::{Rng, distributions::Alphanumeric};
@@ -48,3 +60,64 @@ pub fn random_string(length: usize) -> String {
         .map(|_| rng.sample(Alphanumeric) as char)
         .collect()
 }
+
+/// Converts a decimal amount into integer base units.
+///
+/// # Parameters
+///
+/// * `amount` - Non-negative decimal amount, e.g. `1.5`
+/// * `decimals` - Number of decimals of the currency
+///
+/// # Example
+///
+/// ```
+/// use onechain_wallet_rust_sdk::utils::parse_units;
+///
+/// assert_eq!(parse_units("1.5", 9).unwrap(), 1_500_000_000);
+/// assert!(parse_units("0.0000000001", 9).is_err());
+/// ```
+pub fn parse_units(amount: &str, decimals: u32) -> anyhow::Result<u64> {
+    let amount = amount.trim();
+    let (int, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    anyhow::ensure!(
+        !(int.is_empty() && frac.is_empty())
+            && int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()),
+        "invalid amount {:?}",
+        amount
+    );
+    anyhow::ensure!(
+        frac.len() <= decimals as usize,
+        "amount {} has more than {} decimals",
+        amount,
+        decimals
+    );
+    let digits = format!("{}{:0<width$}", int, frac, width = decimals as usize);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    digits
+        .parse()
+        .map_err(|_| anyhow::anyhow!("amount {} is too large", amount))
+}
+
+/// Formats integer base units as a decimal amount without trailing zeros.
+///
+/// # Example
+///
+/// ```
+/// use onechain_wallet_rust_sdk::utils::format_units;
+///
+/// assert_eq!(format_units(1_500_000_000, 9), "1.5");
+/// assert_eq!(format_units(42, 0), "42");
+/// ```
+pub fn format_units(units: u64, decimals: u32) -> String {
+    let digits = format!("{:0>width$}", units, width = decimals as usize + 1);
+    let (int, frac) = digits.split_at(digits.len() - decimals as usize);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        int.to_string()
+    } else {
+        format!("{}.{}", int, frac)
+    }
+}
@@ -0,0 +1,345 @@
+//! Multisig wallets mixing zkLogin identities and classical keys
+//!
+//! A [`MultiSigPolicy`] assigns weights to its members and requires the signatures of members
+//! whose weights add up to its threshold, e.g. a 2-of-3 treasury policy of a zkLogin identity,
+//! an Ed25519 key and a secp256r1 key. zkLogin members are added with the public identifier
+//! returned by [`zklogin_material`](super::zk_login::zklogin_material).
+//!
+//! Members sign the transaction separately, zkLogin members with
+//! [`zklogin_sign_tx_bytes`](super::zk_login::zklogin_sign_tx_bytes) and classical keys with
+//! [`sign_tx_bytes`]. A [`MultiSigBuilder`] collects the partial signatures and combines them
+//! into the `user_sig` of
+//! [`TransferOrderTxReq`](crate::types::transfer::TransferOrderTxReq) and
+//! [`ProxyPayTxReq`](crate::types::transfer::ProxyPayTxReq).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::sui_types::crypto::{PublicKey, SuiKeyPair};
+//! use onechain_wallet_rust_sdk::utils::multisig::{MultiSigPolicy, sign_tx_bytes};
+//!
+//! fn example(
+//!     zklogin_pk: PublicKey,
+//!     zklogin_sig: String,
+//!     cold_key: &SuiKeyPair,
+//!     backup_pk: PublicKey,
+//!     tx_bytes: &str,
+//! ) -> anyhow::Result<String> {
+//!     let policy = MultiSigPolicy::new(
+//!         vec![(zklogin_pk, 1), (cold_key.public(), 1), (backup_pk, 1)],
+//!         2,
+//!     )?;
+//!     println!("treasury address {}", policy.address());
+//!     let mut builder = policy.builder();
+//!     builder.add_base64(&zklogin_sig)?;
+//!     builder.add_base64(&sign_tx_bytes(cold_key, tx_bytes)?)?;
+//!     builder.finish_base64()
+//! }
+//! ```
+use crate::fastcrypto::encoding::{Base64, Encoding};
+use crate::fastcrypto::traits::EncodeDecodeBase64;
+use crate::shared_crypto::intent::{Intent, IntentMessage};
+use crate::sui_types::base_types::SuiAddress;
+use crate::sui_types::crypto::{PublicKey, Signature, SuiKeyPair, SuiSignature};
+use crate::sui_types::multisig::{MultiSig, MultiSigPublicKey, ThresholdUnit, WeightUnit};
+use crate::sui_types::signature::GenericSignature;
+use crate::sui_types::transaction::TransactionData;
+
+/// Signs a transaction with a classical key
+///
+/// # Parameters
+///
+/// * `skp` - Ed25519, secp256k1 or secp256r1 key pair
+/// * `tx_bytes` - Base64 encoded BCS `TransactionData`
+///
+/// # Return value
+///
+/// Returns the base64 encoded `GenericSignature`
+pub fn sign_tx_bytes<T: AsRef<str>>(skp: &SuiKeyPair, tx_bytes: T) -> anyhow::Result<String> {
+    let tx_data: TransactionData = bcs::from_bytes(&Base64::decode(tx_bytes.as_ref())?)?;
+    let intent_msg = IntentMessage::new(Intent::sui_transaction(), tx_data);
+    let signature: GenericSignature = Signature::new_secure(&intent_msg, skp).into();
+    Ok(signature.encode_base64())
+}
+
+/// Weighted set of public keys and the weight required to sign
+#[derive(Debug, Clone)]
+pub struct MultiSigPolicy {
+    public_key: MultiSigPublicKey,
+}
+
+impl MultiSigPolicy {
+    /// Creates a policy
+    ///
+    /// # Parameters
+    ///
+    /// * `members` - Public keys and their weights, at most 10
+    /// * `threshold` - Total weight of the signatures required
+    ///
+    /// # Error
+    ///
+    /// Returns an error for duplicate or zero-weight members, an empty member list or a
+    /// threshold that is zero or above the total weight
+    pub fn new(
+        members: Vec<(PublicKey, WeightUnit)>,
+        threshold: ThresholdUnit,
+    ) -> anyhow::Result<Self> {
+        let (pks, weights) = members.into_iter().unzip();
+        let public_key = MultiSigPublicKey::new(pks, weights, threshold)
+            .map_err(|e| anyhow::anyhow!("invalid multisig policy: {}", e))?;
+        Ok(Self { public_key })
+    }
+
+    pub fn public_key(&self) -> &MultiSigPublicKey {
+        &self.public_key
+    }
+
+    /// Address of the multisig wallet
+    pub fn address(&self) -> SuiAddress {
+        SuiAddress::from(&self.public_key)
+    }
+
+    pub fn threshold(&self) -> ThresholdUnit {
+        *self.public_key.threshold()
+    }
+
+    /// Weight of a member, `None` for keys outside the policy
+    pub fn weight_of(&self, pk: &PublicKey) -> Option<WeightUnit> {
+        self.public_key
+            .pubkeys()
+            .iter()
+            .find(|(member, _)| member == pk)
+            .map(|(_, weight)| *weight)
+    }
+
+    /// Starts collecting signatures for one transaction
+    pub fn builder(&self) -> MultiSigBuilder {
+        MultiSigBuilder {
+            policy: self.clone(),
+            signatures: Vec::new(),
+            signers: Vec::new(),
+        }
+    }
+}
+
+/// Collects the partial signatures of a [`MultiSigPolicy`]
+///
+/// All partial signatures must be over the same transaction; this is not checked here and a
+/// mismatch is only detected when the transaction is executed.
+#[derive(Debug, Clone)]
+pub struct MultiSigBuilder {
+    policy: MultiSigPolicy,
+    signatures: Vec<GenericSignature>,
+    signers: Vec<PublicKey>,
+}
+
+impl MultiSigBuilder {
+    /// Adds the partial signature of a member
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the signer is not a member, has already signed, or the signature is
+    /// neither a classical nor a zkLogin signature
+    pub fn add(&mut self, signature: GenericSignature) -> anyhow::Result<&mut Self> {
+        let signer = match &signature {
+            GenericSignature::Signature(signature) => {
+                PublicKey::try_from_bytes(signature.scheme(), signature.public_key_bytes())
+                    .map_err(|e| anyhow::anyhow!("invalid public key: {}", e))?
+            }
+            GenericSignature::ZkLoginAuthenticator(authenticator) => authenticator.get_pk()?,
+            _ => anyhow::bail!("multisig members must sign with a classical or zkLogin key"),
+        };
+        anyhow::ensure!(
+            self.policy.weight_of(&signer).is_some(),
+            "signer {} is not a member of the multisig policy",
+            SuiAddress::from(&signer)
+        );
+        anyhow::ensure!(
+            !self.signers.contains(&signer),
+            "signer {} has already signed",
+            SuiAddress::from(&signer)
+        );
+        self.signers.push(signer);
+        self.signatures.push(signature);
+        Ok(self)
+    }
+
+    /// Adds a base64 encoded partial signature, see [`MultiSigBuilder::add`]
+    pub fn add_base64(&mut self, signature: &str) -> anyhow::Result<&mut Self> {
+        let signature = GenericSignature::decode_base64(signature)
+            .map_err(|e| anyhow::anyhow!("invalid signature: {}", e))?;
+        self.add(signature)
+    }
+
+    /// Total weight of the signatures collected so far
+    pub fn weight(&self) -> u32 {
+        self.signers
+            .iter()
+            .filter_map(|signer| self.policy.weight_of(signer))
+            .map(u32::from)
+            .sum()
+    }
+
+    /// Whether the collected signatures reach the threshold
+    pub fn is_complete(&self) -> bool {
+        self.weight() >= u32::from(self.policy.threshold())
+    }
+
+    /// Combines the collected signatures
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the collected signatures do not reach the threshold
+    pub fn finish(&self) -> anyhow::Result<GenericSignature> {
+        anyhow::ensure!(
+            self.is_complete(),
+            "signature weight {} is below the threshold {}",
+            self.weight(),
+            self.policy.threshold()
+        );
+        let multisig = MultiSig::combine(self.signatures.clone(), self.policy.public_key.clone())
+            .map_err(|e| anyhow::anyhow!("failed to combine signatures: {}", e))?;
+        Ok(GenericSignature::MultiSig(multisig))
+    }
+
+    /// Combines the collected signatures into the base64 encoded `user_sig` of a transaction
+    pub fn finish_base64(&self) -> anyhow::Result<String> {
+        Ok(self.finish()?.encode_base64())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fastcrypto::ed25519::{Ed25519KeyPair, Ed25519PrivateKey};
+    use crate::fastcrypto::traits::{KeyPair, ToFromBytes};
+    use crate::fastcrypto_zkp::bn254::zk_login::{OIDCProvider, parse_jwks};
+    use crate::fastcrypto_zkp::bn254::zk_login_api::ZkLoginEnv;
+    use crate::fastcrypto_zkp::zk_login_utils::Bn254FrElement;
+    use crate::shared_crypto::intent::PersonalMessage;
+    use crate::sui_types::crypto::{SignatureScheme, ZkLoginPublicIdentifier};
+    use crate::sui_types::signature::{AuthenticatorTrait, VerifyParams};
+    use crate::sui_types::signature_verification::VerifiedDigestCache;
+    use crate::sui_types::utils::get_zklogin_inputs;
+    use crate::sui_types::zk_login_util::DEFAULT_JWK_BYTES;
+    use crate::utils::ptb::TxBuilder;
+    use crate::utils::zk_login::{EphemeralKeyPair, zklogin_sign_tx_bytes};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    /// Ed25519 key with the private key bytes `[7; 32]`
+    fn cold_key() -> SuiKeyPair {
+        let private = Ed25519PrivateKey::from_bytes(&[7; 32]).unwrap();
+        SuiKeyPair::Ed25519(Ed25519KeyPair::from(private))
+    }
+
+    #[test]
+    fn two_of_three() {
+        let keys = [
+            SignatureScheme::ED25519,
+            SignatureScheme::Secp256r1,
+            SignatureScheme::ED25519,
+        ]
+        .map(|scheme| EphemeralKeyPair::generate(scheme).unwrap());
+        let policy =
+            MultiSigPolicy::new(keys.iter().map(|key| (key.public(), 1)).collect(), 2).unwrap();
+        assert!(MultiSigPolicy::new(vec![(keys[0].public(), 1)], 2).is_err());
+
+        let intent_msg = IntentMessage::new(
+            Intent::personal_message(),
+            PersonalMessage {
+                message: b"treasury".to_vec(),
+            },
+        );
+        let sign = |key: &EphemeralKeyPair| -> GenericSignature {
+            Signature::new_secure(&intent_msg, key.keypair()).into()
+        };
+
+        let mut builder = policy.builder();
+        builder.add(sign(&keys[0])).unwrap();
+        assert!(!builder.is_complete());
+        assert!(builder.finish().is_err());
+        assert!(builder.add(sign(&keys[0])).is_err());
+        let outsider = EphemeralKeyPair::generate(SignatureScheme::ED25519).unwrap();
+        assert!(builder.add(sign(&outsider)).is_err());
+
+        builder.add(sign(&keys[1])).unwrap();
+        assert!(builder.is_complete());
+        assert!(matches!(
+            builder.finish().unwrap(),
+            GenericSignature::MultiSig(_)
+        ));
+    }
+
+    #[test]
+    fn address_known_answer() {
+        let seed = Bn254FrElement::from_str("12345").unwrap();
+        let zklogin_pk = PublicKey::ZkLogin(
+            ZkLoginPublicIdentifier::new("https://accounts.example.com", &seed).unwrap(),
+        );
+        let policy =
+            MultiSigPolicy::new(vec![(zklogin_pk, 1), (cold_key().public(), 1)], 2).unwrap();
+        assert_eq!(
+            policy.address(),
+            SuiAddress::from_str(
+                "0x20cd9d60578f260d143530186337ec2d289a68c1eba274c66888a4e7b873b933"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn zklogin_and_ed25519_sign_transaction() {
+        // Ephemeral key and inputs of the Sui zkLogin test vectors, valid until epoch 10
+        let ephemeral =
+            SuiKeyPair::Ed25519(Ed25519KeyPair::generate(&mut StdRng::from_seed([0; 32])));
+        let inputs = get_zklogin_inputs();
+        let zklogin_pk = PublicKey::ZkLogin(
+            ZkLoginPublicIdentifier::new(inputs.get_iss(), inputs.get_address_seed()).unwrap(),
+        );
+        let cold_key = cold_key();
+        let policy = MultiSigPolicy::new(vec![(zklogin_pk, 1), (cold_key.public(), 1)], 2).unwrap();
+
+        let tx = TxBuilder::new()
+            .finish_data(policy.address(), 10_000_000, 1000)
+            .unwrap();
+        let mut builder = policy.builder();
+        builder
+            .add_base64(&zklogin_sign_tx_bytes(10, &ephemeral, inputs, &tx).unwrap())
+            .unwrap();
+        builder
+            .add_base64(&sign_tx_bytes(&cold_key, &tx).unwrap())
+            .unwrap();
+        let signature = builder.finish().unwrap();
+        assert_eq!(
+            GenericSignature::decode_base64(&builder.finish_base64().unwrap()).unwrap(),
+            signature
+        );
+
+        let tx_data: TransactionData = bcs::from_bytes(&Base64::decode(&tx).unwrap()).unwrap();
+        let intent_msg = IntentMessage::new(Intent::sui_transaction(), tx_data);
+        let jwks = parse_jwks(DEFAULT_JWK_BYTES, &OIDCProvider::Twitch, true).unwrap();
+        let params = VerifyParams::new(
+            jwks.into_iter().collect(),
+            vec![],
+            ZkLoginEnv::Test,
+            true,
+            true,
+            false,
+            None,
+        );
+        let verify = |author: SuiAddress| {
+            signature.verify_authenticator(
+                &intent_msg,
+                author,
+                0,
+                &params,
+                Arc::new(VerifiedDigestCache::new_empty()),
+            )
+        };
+        verify(policy.address()).unwrap();
+        assert!(verify(SuiAddress::from(&cold_key.public())).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+//! Cache for zkLogin proofs
+//!
+//! A proof returned by [`DIDApi::get_zk_proofs`] stays valid until its `max_epoch` has passed,
+//! but computing it is expensive on the server. [`ProofCache`] keeps proofs in memory, keyed by
+//! everything the proof commits to, and optionally persists them through a [`ProofStore`] so
+//! they survive restarts:
+//!
+//! * Proofs are keyed by the JWT `sub`, the salt, the ephemeral public key, `max_epoch` and
+//!   `jwt_randomness`; salt and randomness only enter the key as part of a hash
+//! * Concurrent requests for the same key share one `get_zk_proofs` call
+//! * Proofs are evicted once the current epoch is past their `max_epoch`
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::rpc::DIDApi;
+//! use onechain_wallet_rust_sdk::types::did::ZkProofsReq;
+//! use onechain_wallet_rust_sdk::utils::proof_cache::ProofCache;
+//!
+//! async fn example<T: DIDApi>(client: &T, cache: &ProofCache, req: ZkProofsReq) -> anyhow::Result<()> {
+//!     let current_epoch = 100;
+//!     let reader = cache.get_or_prove(client, req, current_epoch).await?;
+//!     Ok(())
+//! }
+//! ```
+use crate::fastcrypto::encoding::{Encoding, Hex};
+use crate::fastcrypto::hash::{Blake2b256, HashFunction};
+use crate::fastcrypto_zkp::bn254::zk_login::ZkLoginInputsReader;
+use crate::rpc::DIDApi;
+use crate::types::did::ZkProofsReq;
+use crate::utils::zk_login::JwtClaims;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::OnceCell;
+
+/// Identifies a proof by everything it commits to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProofCacheKey {
+    /// Subject of the JWT
+    pub sub: String,
+    /// Extended ephemeral public key
+    pub ephemeral_public_key: String,
+    /// Last epoch in which the proof is accepted
+    pub max_epoch: u64,
+    /// Hex encoded hash over all inputs of the proof request, usable as a storage key
+    pub digest: String,
+}
+
+impl ProofCacheKey {
+    /// Derives the key of a proof request
+    pub fn from_req(req: &ZkProofsReq) -> anyhow::Result<Self> {
+        let claims = JwtClaims::decode(req.jwt.expose_secret())?;
+        let max_epoch = u64::try_from(req.max_epoch)?;
+        let mut hasher = Blake2b256::default();
+        for part in [
+            claims.iss.as_bytes(),
+            claims.aud.as_bytes(),
+            claims.sub.as_bytes(),
+            req.key_claim_name.as_bytes(),
+            req.salt.expose_secret().as_bytes(),
+            req.extended_ephemeral_public_key.as_bytes(),
+            max_epoch.to_be_bytes().as_slice(),
+            req.jwt_randomness.expose_secret().as_bytes(),
+        ] {
+            // Length-prefix every part so that different splits cannot collide
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        Ok(Self {
+            sub: claims.sub,
+            ephemeral_public_key: req.extended_ephemeral_public_key.clone(),
+            max_epoch,
+            digest: Hex::encode(hasher.finalize().digest),
+        })
+    }
+}
+
+/// Persistent backend of a [`ProofCache`]
+///
+/// Proofs are not secret on their own, but together with the ephemeral private key they allow
+/// signing on behalf of the user.
+#[async_trait]
+pub trait ProofStore: Send + Sync {
+    /// Loads a stored proof, `None` if there is none
+    async fn load(&self, key: &ProofCacheKey) -> anyhow::Result<Option<ZkLoginInputsReader>>;
+
+    /// Stores a proof
+    async fn save(&self, key: &ProofCacheKey, proof: &ZkLoginInputsReader) -> anyhow::Result<()>;
+
+    /// Removes all proofs whose `max_epoch` is before `current_epoch`
+    async fn evict_expired(&self, current_epoch: u64) -> anyhow::Result<()>;
+}
+
+type Entries = HashMap<ProofCacheKey, Arc<OnceCell<ZkLoginInputsReader>>>;
+
+/// In-memory proof cache with an optional [`ProofStore`], see the [module documentation](self)
+#[derive(Default)]
+pub struct ProofCache {
+    entries: Mutex<Entries>,
+    store: Option<Arc<dyn ProofStore>>,
+}
+
+impl ProofCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a cache that loads missing proofs from and saves new proofs to `store`
+    pub fn with_store(store: Arc<dyn ProofStore>) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            store: Some(store),
+        }
+    }
+
+    /// Returns the cached proof for `req`, requesting it from `client` if there is none
+    ///
+    /// # Parameters
+    ///
+    /// * `client` - API client used on cache misses
+    /// * `req` - Proof request
+    /// * `current_epoch` - Current epoch, proofs expiring before it are evicted
+    ///
+    /// # Error
+    ///
+    /// Returns an error if `req.max_epoch` is before `current_epoch`, in which case the proof
+    /// would be rejected anyway, or if the proof cannot be obtained. Failed requests are not
+    /// cached.
+    pub async fn get_or_prove<T: DIDApi>(
+        &self,
+        client: &T,
+        req: ZkProofsReq,
+        current_epoch: u64,
+    ) -> anyhow::Result<ZkLoginInputsReader> {
+        let key = ProofCacheKey::from_req(&req)?;
+        anyhow::ensure!(
+            key.max_epoch >= current_epoch,
+            "max epoch {} is before current epoch {}",
+            key.max_epoch,
+            current_epoch
+        );
+        self.evict_expired(current_epoch).await?;
+
+        let cell = self.lock().entry(key.clone()).or_default().clone();
+        let result = cell
+            .get_or_try_init(|| async {
+                let stored = match &self.store {
+                    Some(store) => store.load(&key).await?,
+                    None => None,
+                };
+                if let Some(proof) = stored {
+                    return Ok(proof);
+                }
+                let proof = client.get_zk_proofs(req).await?.into_result()?;
+                if let Some(store) = &self.store {
+                    store.save(&key, &proof).await?;
+                }
+                anyhow::Ok(proof)
+            })
+            .await;
+        match result {
+            Ok(proof) => Ok(proof.clone()),
+            Err(e) => {
+                // Drop the empty cell so that failures do not accumulate entries, unless it was
+                // replaced or another caller has since filled it
+                let mut entries = self.lock();
+                if entries
+                    .get(&key)
+                    .is_some_and(|entry| Arc::ptr_eq(entry, &cell) && !entry.initialized())
+                {
+                    entries.remove(&key);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the cached proof for `key` without requesting it
+    pub fn get(&self, key: &ProofCacheKey) -> Option<ZkLoginInputsReader> {
+        self.lock().get(key)?.get().cloned()
+    }
+
+    /// Removes the proof for `key` from memory, the store is left untouched
+    pub fn remove(&self, key: &ProofCacheKey) {
+        self.lock().remove(key);
+    }
+
+    /// Removes all proofs whose `max_epoch` is before `current_epoch`
+    pub async fn evict_expired(&self, current_epoch: u64) -> anyhow::Result<()> {
+        self.lock().retain(|key, _| key.max_epoch >= current_epoch);
+        if let Some(store) = &self.store {
+            store.evict_expired(current_epoch).await?;
+        }
+        Ok(())
+    }
+
+    /// Number of proofs held in memory, including requests in flight
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Debug for ProofCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProofCache")
+            .field("len", &self.len())
+            .field("persistent", &self.store.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{MockClient, fail, ok};
+    use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+    use serde_json::{Value, json};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const PROOF_PATH: &'static str = "/did/getZkProofs";
+
+    fn req(max_epoch: i64) -> ZkProofsReq {
+        let payload = json!({"iss": "iss", "aud": "aud", "sub": "123"});
+        let jwt = format!(
+            "e30.{}.c2ln",
+            BASE64_URL_SAFE_NO_PAD.encode(payload.to_string())
+        );
+        ZkProofsReq {
+            max_epoch,
+            jwt_randomness: "1".into(),
+            extended_ephemeral_public_key: "2".into(),
+            jwt: jwt.into(),
+            salt: "3".into(),
+            ..Default::default()
+        }
+    }
+
+    fn proof() -> Value {
+        json!({
+            "proofPoints": {
+                "a": ["1", "2", "1"],
+                "b": [["1", "2"], ["3", "4"], ["1", "0"]],
+                "c": ["1", "2", "1"],
+            },
+            "issBase64Details": {"value": "wiaXNzIjoiaXNzIiw", "indexMod4": 1},
+            "headerBase64": "eyJhbGciOiJSUzI1NiJ9",
+        })
+    }
+
+    /// Client answering every proof request with [`proof`]
+    fn prover() -> MockClient {
+        MockClient::new(|_, _| ok(proof()))
+    }
+
+    #[derive(Default)]
+    struct MemoryStore {
+        proofs: Mutex<HashMap<String, ZkLoginInputsReader>>,
+        saves: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ProofStore for MemoryStore {
+        async fn load(&self, key: &ProofCacheKey) -> anyhow::Result<Option<ZkLoginInputsReader>> {
+            Ok(self.proofs.lock().unwrap().get(&key.digest).cloned())
+        }
+
+        async fn save(
+            &self,
+            key: &ProofCacheKey,
+            proof: &ZkLoginInputsReader,
+        ) -> anyhow::Result<()> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            self.proofs
+                .lock()
+                .unwrap()
+                .insert(key.digest.clone(), proof.clone());
+            Ok(())
+        }
+
+        async fn evict_expired(&self, _current_epoch: u64) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn key_covers_all_inputs() {
+        let req = req(10);
+        let key = ProofCacheKey::from_req(&req).unwrap();
+        assert_eq!(key.sub, "123");
+        assert_eq!(key.max_epoch, 10);
+        assert_eq!(key, ProofCacheKey::from_req(&req.clone()).unwrap());
+
+        let mut other = req.clone();
+        other.jwt_randomness = "4".into();
+        assert_ne!(ProofCacheKey::from_req(&other).unwrap(), key);
+        let mut other = req.clone();
+        other.salt = "4".into();
+        assert_ne!(ProofCacheKey::from_req(&other).unwrap(), key);
+        let mut other = req;
+        other.max_epoch = -1;
+        assert!(ProofCacheKey::from_req(&other).is_err());
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_call() {
+        let client = prover().with_delay(Duration::from_millis(50));
+        let cache = ProofCache::new();
+        let (a, b, c) = tokio::join!(
+            cache.get_or_prove(&client, req(10), 5),
+            cache.get_or_prove(&client, req(10), 5),
+            cache.get_or_prove(&client, req(10), 5),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(client.count(PROOF_PATH), 1);
+
+        cache.get_or_prove(&client, req(10), 5).await.unwrap();
+        assert_eq!(client.count(PROOF_PATH), 1);
+        assert!(
+            cache
+                .get(&ProofCacheKey::from_req(&req(10)).unwrap())
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn evicts_after_max_epoch() {
+        let client = prover();
+        let cache = ProofCache::new();
+        cache.get_or_prove(&client, req(10), 5).await.unwrap();
+        cache.get_or_prove(&client, req(20), 5).await.unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // The proof for max epoch 10 is still valid in epoch 10
+        cache.get_or_prove(&client, req(20), 10).await.unwrap();
+        assert_eq!(cache.len(), 2);
+        cache.get_or_prove(&client, req(20), 11).await.unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(
+            cache
+                .get(&ProofCacheKey::from_req(&req(10)).unwrap())
+                .is_none()
+        );
+
+        let err = cache.get_or_prove(&client, req(10), 11).await.unwrap_err();
+        assert!(err.to_string().contains("before current epoch"));
+        assert_eq!(client.count(PROOF_PATH), 2);
+    }
+
+    #[tokio::test]
+    async fn store_load_and_save() {
+        let client = prover();
+        let store = Arc::new(MemoryStore::default());
+        let cache = ProofCache::with_store(store.clone());
+        cache.get_or_prove(&client, req(10), 5).await.unwrap();
+        assert_eq!(client.count(PROOF_PATH), 1);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+
+        // A new cache, e.g. after a restart, loads the proof from the store
+        let cache = ProofCache::with_store(store.clone());
+        let proof = cache.get_or_prove(&client, req(10), 5).await.unwrap();
+        assert_eq!(
+            format!("{:?}", proof),
+            format!(
+                "{:?}",
+                store.proofs.lock().unwrap().values().next().unwrap()
+            )
+        );
+        assert_eq!(client.count(PROOF_PATH), 1);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_requests_not_cached() {
+        let attempts = AtomicUsize::new(0);
+        let client = MockClient::new(move |_, _| {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                fail("500000", "prover unavailable")
+            } else {
+                ok(proof())
+            }
+        });
+        let store = Arc::new(MemoryStore::default());
+        let cache = ProofCache::with_store(store.clone());
+        assert!(cache.get_or_prove(&client, req(10), 5).await.is_err());
+        assert!(cache.is_empty());
+        assert_eq!(store.saves.load(Ordering::SeqCst), 0);
+
+        cache.get_or_prove(&client, req(10), 5).await.unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(client.count(PROOF_PATH), 2);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,370 @@
+//! Programmable transaction helpers for sponsored transactions
+//!
+//! [`TxBuilder`] wraps [`ProgrammableTransactionBuilder`] with the commands most wallets need
+//! and serializes the result in the form expected by
+//! [`TransferApi::build_sponsor_tx`](crate::rpc::TransferApi::build_sponsor_tx):
+//!
+//! * [`SponsorTxForm::Kind`] - base64 BCS `TransactionKind`, see [`TxBuilder::finish_kind`]
+//! * [`SponsorTxForm::Data`] - base64 BCS `TransactionData` without gas payment, see
+//!   [`TxBuilder::finish_data`]
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::sui_types::base_types::{ObjectRef, SuiAddress};
+//! use onechain_wallet_rust_sdk::types::transfer::BuildSponsorTxReq;
+//! use onechain_wallet_rust_sdk::utils::ptb::{MoveCallArg, SponsorTxForm, TxBuilder};
+//!
+//! fn example(sender: SuiAddress, coin: ObjectRef, to: SuiAddress) -> anyhow::Result<BuildSponsorTxReq> {
+//!     let mut tx = TxBuilder::new();
+//!     tx.split_coin(coin, vec![1_000_000], to)?;
+//!     tx.move_call(
+//!         "0x2",
+//!         "event",
+//!         "emit",
+//!         &[],
+//!         vec![MoveCallArg::pure(&42u64)?],
+//!     )?;
+//!     tx.sponsor_req(sender, SponsorTxForm::Kind { gas_budget: None })
+//! }
+//! ```
+use crate::fastcrypto::encoding::{Base64, Encoding};
+use crate::sui_types::base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress};
+use crate::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use crate::sui_types::transaction::{
+    Argument, CallArg, Command, ObjectArg, ProgrammableTransaction, TransactionData,
+    TransactionKind,
+};
+use crate::sui_types::{Identifier, parse_sui_type_tag};
+use crate::types::transfer::BuildSponsorTxReq;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Form of the transaction sent to [`TransferApi::build_sponsor_tx`](crate::rpc::TransferApi::build_sponsor_tx)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SponsorTxForm {
+    /// `TransactionKind`, the sponsor fills in the gas data
+    Kind {
+        /// Gas limit passed to the sponsor, e.g. `0.01`, the server default if `None`
+        gas_budget: Option<String>,
+    },
+    /// `TransactionData` without gas payment
+    Data {
+        /// Gas budget, in MIST
+        gas_budget: u64,
+        /// Gas price, in MIST, e.g. from
+        /// [`ChainInfo::reference_gas_price`](crate::utils::chain::ChainInfo::reference_gas_price)
+        gas_price: u64,
+    },
+}
+
+/// Argument of a Move call
+#[derive(Debug, Clone)]
+pub enum MoveCallArg {
+    /// BCS encoded pure value
+    Pure(Vec<u8>),
+    /// Owned or immutable object
+    Object(ObjectRef),
+    /// Shared object
+    Shared {
+        id: ObjectID,
+        initial_shared_version: SequenceNumber,
+        mutable: bool,
+    },
+}
+
+impl MoveCallArg {
+    /// BCS encodes a pure value, e.g. `u64`, `bool`, `SuiAddress`, `String` or `Vec<u8>`
+    pub fn pure<T: Serialize>(value: &T) -> anyhow::Result<Self> {
+        Ok(MoveCallArg::Pure(bcs::to_bytes(value)?))
+    }
+}
+
+impl From<MoveCallArg> for CallArg {
+    fn from(arg: MoveCallArg) -> Self {
+        match arg {
+            MoveCallArg::Pure(bytes) => CallArg::Pure(bytes),
+            MoveCallArg::Object(object) => CallArg::Object(ObjectArg::ImmOrOwnedObject(object)),
+            MoveCallArg::Shared {
+                id,
+                initial_shared_version,
+                mutable,
+            } => CallArg::Object(ObjectArg::SharedObject {
+                id,
+                initial_shared_version,
+                mutable,
+            }),
+        }
+    }
+}
+
+/// Builder of programmable transactions, see the [module documentation](self)
+#[derive(Default)]
+pub struct TxBuilder {
+    builder: ProgrammableTransactionBuilder,
+}
+
+impl TxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives access to the wrapped builder for commands without a helper
+    pub fn inner(&mut self) -> &mut ProgrammableTransactionBuilder {
+        &mut self.builder
+    }
+
+    /// Pays `amounts` from `coins` to `recipients`, the first coin receives the change
+    pub fn pay(
+        &mut self,
+        coins: Vec<ObjectRef>,
+        recipients: Vec<SuiAddress>,
+        amounts: Vec<u64>,
+    ) -> anyhow::Result<&mut Self> {
+        self.builder.pay(coins, recipients, amounts)?;
+        Ok(self)
+    }
+
+    /// Splits coins of the given amounts off `coin` and transfers them to `recipient`
+    pub fn split_coin(
+        &mut self,
+        coin: ObjectRef,
+        amounts: Vec<u64>,
+        recipient: SuiAddress,
+    ) -> anyhow::Result<&mut Self> {
+        let coin = self.builder.obj(ObjectArg::ImmOrOwnedObject(coin))?;
+        let count = amounts.len();
+        let amounts = amounts
+            .into_iter()
+            .map(|amount| self.builder.pure(amount))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let Argument::Result(split) = self.builder.command(Command::SplitCoins(coin, amounts))
+        else {
+            anyhow::bail!("unexpected result of split coins");
+        };
+        let count = u16::try_from(count)
+            .map_err(|_| anyhow::anyhow!("cannot split more than {} coins", u16::MAX))?;
+        let coins = (0..count)
+            .map(|index| Argument::NestedResult(split, index))
+            .collect();
+        self.builder.transfer_args(recipient, coins);
+        Ok(self)
+    }
+
+    /// Merges `coins` into `primary`
+    pub fn merge_coins(
+        &mut self,
+        primary: ObjectRef,
+        coins: Vec<ObjectRef>,
+    ) -> anyhow::Result<&mut Self> {
+        anyhow::ensure!(!coins.is_empty(), "no coins to merge");
+        let primary = self.builder.obj(ObjectArg::ImmOrOwnedObject(primary))?;
+        let coins = coins
+            .into_iter()
+            .map(|coin| self.builder.obj(ObjectArg::ImmOrOwnedObject(coin)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.builder.command(Command::MergeCoins(primary, coins));
+        Ok(self)
+    }
+
+    /// Transfers an owned object to `recipient`
+    pub fn transfer_object(
+        &mut self,
+        recipient: SuiAddress,
+        object: ObjectRef,
+    ) -> anyhow::Result<&mut Self> {
+        self.builder.transfer_object(recipient, object)?;
+        Ok(self)
+    }
+
+    /// Calls a Move function
+    ///
+    /// # Parameters
+    ///
+    /// * `package` - Package ID, e.g. `0x2`
+    /// * `module` - Module name
+    /// * `function` - Function name
+    /// * `type_arguments` - Type arguments, e.g. `0x2::oct::OCT`
+    /// * `arguments` - Call arguments
+    pub fn move_call(
+        &mut self,
+        package: &str,
+        module: &str,
+        function: &str,
+        type_arguments: &[&str],
+        arguments: Vec<MoveCallArg>,
+    ) -> anyhow::Result<&mut Self> {
+        let type_arguments = type_arguments
+            .iter()
+            .map(|type_tag| parse_sui_type_tag(type_tag))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.builder.move_call(
+            ObjectID::from_str(package)?,
+            Identifier::new(module)?,
+            Identifier::new(function)?,
+            type_arguments,
+            arguments.into_iter().map(CallArg::from).collect(),
+        )?;
+        Ok(self)
+    }
+
+    /// Returns the programmable transaction
+    pub fn finish(self) -> ProgrammableTransaction {
+        self.builder.finish()
+    }
+
+    /// Serializes the transaction as base64 BCS `TransactionKind`
+    pub fn finish_kind(self) -> anyhow::Result<String> {
+        let kind = TransactionKind::ProgrammableTransaction(self.finish());
+        Ok(Base64::encode(bcs::to_bytes(&kind)?))
+    }
+
+    /// Serializes the transaction as base64 BCS `TransactionData` without gas payment
+    pub fn finish_data(
+        self,
+        sender: SuiAddress,
+        gas_budget: u64,
+        gas_price: u64,
+    ) -> anyhow::Result<String> {
+        let data =
+            TransactionData::new_programmable(sender, vec![], self.finish(), gas_budget, gas_price);
+        Ok(Base64::encode(bcs::to_bytes(&data)?))
+    }
+
+    /// Builds the request of [`TransferApi::build_sponsor_tx`](crate::rpc::TransferApi::build_sponsor_tx)
+    ///
+    /// # Parameters
+    ///
+    /// * `sender` - Sender address
+    /// * `form` - Whether to send a `TransactionKind` or a `TransactionData`, each with its
+    ///   gas budget. The budget of a `TransactionData` is part of the transaction, so the
+    ///   request's `gas_budget` is left unset
+    pub fn sponsor_req(
+        self,
+        sender: SuiAddress,
+        form: SponsorTxForm,
+    ) -> anyhow::Result<BuildSponsorTxReq> {
+        let (raw_transaction, only_transaction_kind, gas_budget) = match form {
+            SponsorTxForm::Kind { gas_budget } => (self.finish_kind()?, true, gas_budget),
+            SponsorTxForm::Data {
+                gas_budget,
+                gas_price,
+            } => (
+                self.finish_data(sender, gas_budget, gas_price)?,
+                false,
+                None,
+            ),
+        };
+        Ok(BuildSponsorTxReq {
+            address: sender,
+            raw_transaction,
+            only_transaction_kind,
+            gas_budget,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sui_types::digests::ObjectDigest;
+    use crate::sui_types::transaction::TransactionDataAPI;
+
+    fn object() -> ObjectRef {
+        (
+            ObjectID::random(),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::random(),
+        )
+    }
+
+    #[test]
+    fn sponsor_req_forms() {
+        let sender = SuiAddress::random_for_testing_only();
+        let build = || {
+            let mut tx = TxBuilder::new();
+            tx.split_coin(object(), vec![1, 2], sender).unwrap();
+            tx.merge_coins(object(), vec![object()]).unwrap();
+            tx.move_call("0x2", "coin", "zero", &["0x2::oct::OCT"], vec![])
+                .unwrap();
+            tx
+        };
+
+        let form = SponsorTxForm::Kind {
+            gas_budget: Some("0.01".to_string()),
+        };
+        let req = build().sponsor_req(sender, form).unwrap();
+        assert!(req.only_transaction_kind);
+        assert_eq!(req.gas_budget.as_deref(), Some("0.01"));
+        let kind: TransactionKind =
+            bcs::from_bytes(&Base64::decode(&req.raw_transaction).unwrap()).unwrap();
+        let TransactionKind::ProgrammableTransaction(pt) = kind else {
+            panic!("expected a programmable transaction");
+        };
+        assert_eq!(pt.commands.len(), 4);
+
+        let form = SponsorTxForm::Data {
+            gas_budget: 5_000_000,
+            gas_price: 750,
+        };
+        let req = build().sponsor_req(sender, form).unwrap();
+        assert!(!req.only_transaction_kind);
+        assert_eq!(req.gas_budget, None);
+        let data: TransactionData =
+            bcs::from_bytes(&Base64::decode(&req.raw_transaction).unwrap()).unwrap();
+        assert_eq!(data.sender(), sender);
+        assert_eq!(data.gas_budget(), 5_000_000);
+        assert_eq!(data.gas_price(), 750);
+    }
+
+    fn commands(tx: TxBuilder) -> Vec<Command> {
+        tx.finish().commands
+    }
+
+    #[test]
+    fn pay_and_transfer_object() {
+        let recipients = [
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+        ];
+        let mut tx = TxBuilder::new();
+        tx.pay(vec![object(), object()], recipients.to_vec(), vec![10, 20])
+            .unwrap();
+        let commands = commands(tx);
+        assert!(matches!(&commands[0], Command::MergeCoins(_, coins) if coins.len() == 1));
+        assert!(matches!(&commands[1], Command::SplitCoins(_, amounts) if amounts.len() == 2));
+        assert_eq!(
+            commands
+                .iter()
+                .filter(|command| matches!(command, Command::TransferObjects(..)))
+                .count(),
+            2
+        );
+
+        let mut tx = TxBuilder::new();
+        assert!(tx.pay(vec![], recipients.to_vec(), vec![10, 20]).is_err());
+        assert!(
+            tx.pay(vec![object()], recipients.to_vec(), vec![10])
+                .is_err()
+        );
+
+        let mut tx = TxBuilder::new();
+        tx.transfer_object(recipients[0], object()).unwrap();
+        let pt = tx.finish();
+        assert_eq!(pt.inputs.len(), 2);
+        assert!(matches!(
+            &pt.commands[..],
+            [Command::TransferObjects(objects, _)] if objects.len() == 1
+        ));
+    }
+
+    #[test]
+    fn split_coin_count_limit() {
+        let mut tx = TxBuilder::new();
+        let amounts = vec![1; usize::from(u16::MAX) + 1];
+        let err = tx
+            .split_coin(object(), amounts, SuiAddress::random_for_testing_only())
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot split more than"));
+    }
+}
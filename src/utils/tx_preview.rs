@@ -0,0 +1,470 @@
+//! Human-readable previews of transactions
+//!
+//! Before a user signs the `raw_transaction` of
+//! [`CreateOrderResp`](crate::types::common::CreateOrderResp) or
+//! [`GasTxBuilderResponse`](crate::types::transfer::GasTxBuilderResponse), a front-end should
+//! show what the transaction does. [`TxPreviewer`] decodes base64 `TransactionData` into a
+//! [`TxPreview`] that serializes to JSON:
+//!
+//! * Sender, gas owner, gas budget and gas price
+//! * Inputs and commands, with Move call targets as `package::module::function`
+//! * Coin transfers, found by following split coins into transfer commands, with amounts
+//!   formatted with the decimals and symbol of the [`CurrencyRegistry`]
+//!
+//! Only `TransferObjects` commands are listed as transfers. Value moved by Move calls, e.g.
+//! `0x2::pay::split_and_transfer` or `0x2::transfer::public_transfer`, does not show up in
+//! [`TxPreview::transfers`]; show [`TxPreview::move_calls`] alongside them.
+//!
+//! The transaction only refers to coins by object ID, so the coin type of input coins must be
+//! passed with [`TxPreviewer::coin_type`]; coins split off the gas coin are always
+//! [`GAS_COIN_TYPE`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::rpc::WalletApi;
+//! use onechain_wallet_rust_sdk::utils::tx_preview::{CurrencyRegistry, TxPreviewer};
+//!
+//! async fn example<T: WalletApi>(client: &T, raw_transaction: &str) -> anyhow::Result<String> {
+//!     let chains = client.query_chain_currency_for_list().await?.into_result()?;
+//!     let previewer = TxPreviewer::new(CurrencyRegistry::from_chains(&chains));
+//!     let preview = previewer.preview(raw_transaction)?;
+//!     Ok(serde_json::to_string_pretty(&preview)?)
+//! }
+//! ```
+use crate::fastcrypto::encoding::{Base64, Encoding, Hex};
+use crate::sui_types::base_types::{ObjectID, SuiAddress};
+use crate::sui_types::parse_sui_type_tag;
+use crate::sui_types::transaction::{
+    Argument, CallArg, Command, ObjectArg, TransactionData, TransactionDataAPI,
+    TransactionExpiration, TransactionKind,
+};
+use crate::types::wallet::{CurrencyChainResp, CurrencyInfo};
+use crate::utils::format_units;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Coin type of the gas coin, also the default coin type of batch transfers
+pub const GAS_COIN_TYPE: &'static str = "0x2::oct::OCT";
+
+/// Normalizes a coin type so that short and long addresses compare equal
+fn normalize_coin_type(coin_type: &str) -> String {
+    parse_sui_type_tag(coin_type)
+        .map(|type_tag| type_tag.to_canonical_string(true))
+        .unwrap_or_else(|_| coin_type.to_string())
+}
+
+/// Currencies by coin type
+#[derive(Debug, Clone, Default)]
+pub struct CurrencyRegistry {
+    currencies: HashMap<String, CurrencyInfo>,
+}
+
+impl CurrencyRegistry {
+    pub fn new(currencies: impl IntoIterator<Item = CurrencyInfo>) -> Self {
+        Self {
+            currencies: currencies
+                .into_iter()
+                .map(|currency| (normalize_coin_type(&currency.coin_type), currency))
+                .collect(),
+        }
+    }
+
+    /// Collects the currencies of all chains returned by
+    /// [`WalletApi::query_chain_currency_for_list`](crate::rpc::WalletApi::query_chain_currency_for_list)
+    pub fn from_chains(chains: &[CurrencyChainResp]) -> Self {
+        Self::new(
+            chains
+                .iter()
+                .flat_map(|chain| chain.currency_list.iter().cloned()),
+        )
+    }
+
+    /// Looks up a currency by coin type, e.g. `0x2::oct::OCT`
+    pub fn get(&self, coin_type: &str) -> Option<&CurrencyInfo> {
+        self.currencies.get(&normalize_coin_type(coin_type))
+    }
+
+    /// Describes `raw` base units of `coin_type`
+    pub fn amount(&self, coin_type: Option<&str>, raw: u64) -> CoinAmount {
+        let currency = coin_type.and_then(|coin_type| self.get(coin_type));
+        CoinAmount {
+            raw: raw.to_string(),
+            coin_type: coin_type.map(str::to_string),
+            symbol: currency.and_then(|currency| currency.symbol.clone()),
+            formatted: currency
+                .and_then(|currency| u32::try_from(currency.calculate_decimals).ok())
+                .map(|decimals| format_units(raw, decimals)),
+        }
+    }
+}
+
+/// Amount of a coin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoinAmount {
+    /// Amount in base units, as string since it may exceed the JavaScript number range
+    pub raw: String,
+    /// Coin type, `None` if unknown
+    pub coin_type: Option<String>,
+    /// Currency symbol, `None` if the coin type is not in the registry
+    pub symbol: Option<String>,
+    /// Amount with the currency decimals applied, e.g. `1.5`
+    pub formatted: Option<String>,
+}
+
+/// Transaction input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum InputPreview {
+    /// BCS encoded value
+    Pure { bytes: String },
+    /// Owned or immutable object
+    Object { id: ObjectID, version: u64 },
+    /// Shared object
+    Shared {
+        id: ObjectID,
+        initial_shared_version: u64,
+        mutable: bool,
+    },
+    /// Object sent to an object, received by the transaction
+    Receiving { id: ObjectID, version: u64 },
+}
+
+/// Transaction command
+///
+/// Arguments refer to the gas coin, to inputs by index or to results of earlier commands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CommandPreview {
+    /// Calls `target`, e.g. `0x2::coin::split`
+    MoveCall {
+        target: String,
+        type_arguments: Vec<String>,
+        arguments: Vec<Argument>,
+    },
+    TransferObjects {
+        objects: Vec<Argument>,
+        recipient: Argument,
+    },
+    SplitCoins {
+        coin: Argument,
+        amounts: Vec<Argument>,
+    },
+    MergeCoins {
+        coin: Argument,
+        sources: Vec<Argument>,
+    },
+    MakeMoveVec {
+        type_argument: Option<String>,
+        elements: Vec<Argument>,
+    },
+    /// Publishes a package
+    Publish {
+        modules: usize,
+        dependencies: Vec<ObjectID>,
+    },
+    /// Upgrades `package`
+    Upgrade {
+        modules: usize,
+        dependencies: Vec<ObjectID>,
+        package: ObjectID,
+    },
+}
+
+/// Object or coin amount sent to a recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferPreview {
+    /// Recipient, `None` if it is not a pure input, e.g. computed by a Move call
+    pub recipient: Option<SuiAddress>,
+    /// Transferred input object, `None` for coins split off in the transaction
+    pub object: Option<ObjectID>,
+    /// Amount, `None` if the object is not a coin split off in the transaction
+    pub amount: Option<CoinAmount>,
+}
+
+/// Structured description of a transaction, see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxPreview {
+    pub sender: SuiAddress,
+    /// Address paying for gas, differs from the sender for sponsored transactions
+    pub gas_owner: SuiAddress,
+    pub gas_budget: CoinAmount,
+    pub gas_price: String,
+    /// Gas coins
+    pub gas_payment: Vec<ObjectID>,
+    /// Last epoch in which the transaction can execute, if limited
+    pub expiration_epoch: Option<u64>,
+    pub inputs: Vec<InputPreview>,
+    pub commands: Vec<CommandPreview>,
+    /// Objects sent by `TransferObjects` commands, with amounts for coins split off by
+    /// `SplitCoins`; transfers made by Move calls are not included
+    pub transfers: Vec<TransferPreview>,
+    /// Targets of all Move calls
+    pub move_calls: Vec<String>,
+}
+
+/// Decodes transactions into [`TxPreview`]s
+#[derive(Debug, Clone, Default)]
+pub struct TxPreviewer {
+    registry: CurrencyRegistry,
+    coin_types: HashMap<ObjectID, String>,
+}
+
+impl TxPreviewer {
+    pub fn new(registry: CurrencyRegistry) -> Self {
+        Self {
+            registry,
+            coin_types: HashMap::new(),
+        }
+    }
+
+    /// Sets the coin type of an input coin, e.g. from the coin type of an order
+    pub fn coin_type(mut self, coin: ObjectID, coin_type: impl Into<String>) -> Self {
+        self.coin_types.insert(coin, coin_type.into());
+        self
+    }
+
+    pub fn registry(&self) -> &CurrencyRegistry {
+        &self.registry
+    }
+
+    /// Decodes base64 encoded BCS `TransactionData`
+    ///
+    /// # Error
+    ///
+    /// Returns an error for invalid transaction bytes or a transaction that is not a
+    /// programmable transaction
+    pub fn preview(&self, tx_bytes: &str) -> anyhow::Result<TxPreview> {
+        let tx_data: TransactionData = bcs::from_bytes(&Base64::decode(tx_bytes)?)?;
+        self.preview_data(&tx_data)
+    }
+
+    /// Describes decoded `TransactionData`
+    pub fn preview_data(&self, tx_data: &TransactionData) -> anyhow::Result<TxPreview> {
+        let TransactionKind::ProgrammableTransaction(pt) = tx_data.kind() else {
+            anyhow::bail!("only programmable transactions can be previewed");
+        };
+        let inputs = pt.inputs.iter().map(input_preview).collect();
+        let commands = pt.commands.iter().map(command_preview).collect::<Vec<_>>();
+        let move_calls = commands
+            .iter()
+            .filter_map(|command| match command {
+                CommandPreview::MoveCall { target, .. } => Some(target.clone()),
+                _ => None,
+            })
+            .collect();
+        Ok(TxPreview {
+            sender: tx_data.sender(),
+            gas_owner: tx_data.gas_owner(),
+            gas_budget: self
+                .registry
+                .amount(Some(GAS_COIN_TYPE), tx_data.gas_budget()),
+            gas_price: tx_data.gas_price().to_string(),
+            gas_payment: tx_data.gas().iter().map(|(id, _, _)| *id).collect(),
+            expiration_epoch: match tx_data.expiration() {
+                TransactionExpiration::Epoch(epoch) => Some(*epoch),
+                _ => None,
+            },
+            transfers: self.transfers(&pt.inputs, &pt.commands),
+            inputs,
+            commands,
+            move_calls,
+        })
+    }
+
+    /// Follows split coins into transfer commands
+    fn transfers(&self, inputs: &[CallArg], commands: &[Command]) -> Vec<TransferPreview> {
+        let pure_u64 = |arg: &Argument| match arg {
+            Argument::Input(index) => match inputs.get(*index as usize) {
+                Some(CallArg::Pure(bytes)) => bcs::from_bytes::<u64>(bytes).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+        let input_object = |arg: &Argument| match arg {
+            Argument::Input(index) => match inputs.get(*index as usize) {
+                Some(CallArg::Object(ObjectArg::ImmOrOwnedObject((id, _, _)))) => Some(*id),
+                _ => None,
+            },
+            _ => None,
+        };
+        let coin_type = |coin: &Argument| match coin {
+            Argument::GasCoin => Some(GAS_COIN_TYPE.to_string()),
+            _ => input_object(coin).and_then(|id| self.coin_types.get(&id).cloned()),
+        };
+        let split = |command: u16, index: u16| match commands.get(command as usize) {
+            Some(Command::SplitCoins(coin, amounts)) => Some(self.registry.amount(
+                coin_type(coin).as_deref(),
+                pure_u64(amounts.get(index as usize)?)?,
+            )),
+            _ => None,
+        };
+
+        let mut transfers = Vec::new();
+        for command in commands {
+            let Command::TransferObjects(objects, recipient) = command else {
+                continue;
+            };
+            let recipient = match recipient {
+                Argument::Input(index) => match inputs.get(*index as usize) {
+                    Some(CallArg::Pure(bytes)) => bcs::from_bytes::<SuiAddress>(bytes).ok(),
+                    _ => None,
+                },
+                _ => None,
+            };
+            for object in objects {
+                let amount = match object {
+                    Argument::Result(command) => split(*command, 0),
+                    Argument::NestedResult(command, index) => split(*command, *index),
+                    _ => None,
+                };
+                transfers.push(TransferPreview {
+                    recipient,
+                    object: input_object(object),
+                    amount,
+                });
+            }
+        }
+        transfers
+    }
+}
+
+fn input_preview(input: &CallArg) -> InputPreview {
+    match input {
+        CallArg::Pure(bytes) => InputPreview::Pure {
+            bytes: Hex::encode(bytes),
+        },
+        CallArg::Object(ObjectArg::ImmOrOwnedObject((id, version, _))) => InputPreview::Object {
+            id: *id,
+            version: version.value(),
+        },
+        CallArg::Object(ObjectArg::SharedObject {
+            id,
+            initial_shared_version,
+            mutable,
+        }) => InputPreview::Shared {
+            id: *id,
+            initial_shared_version: initial_shared_version.value(),
+            mutable: *mutable,
+        },
+        CallArg::Object(ObjectArg::Receiving((id, version, _))) => InputPreview::Receiving {
+            id: *id,
+            version: version.value(),
+        },
+    }
+}
+
+fn command_preview(command: &Command) -> CommandPreview {
+    match command {
+        Command::MoveCall(call) => CommandPreview::MoveCall {
+            target: format!("{}::{}::{}", call.package, call.module, call.function),
+            type_arguments: call
+                .type_arguments
+                .iter()
+                .map(|type_tag| type_tag.to_canonical_string(true))
+                .collect(),
+            arguments: call.arguments.clone(),
+        },
+        Command::TransferObjects(objects, recipient) => CommandPreview::TransferObjects {
+            objects: objects.clone(),
+            recipient: *recipient,
+        },
+        Command::SplitCoins(coin, amounts) => CommandPreview::SplitCoins {
+            coin: *coin,
+            amounts: amounts.clone(),
+        },
+        Command::MergeCoins(coin, sources) => CommandPreview::MergeCoins {
+            coin: *coin,
+            sources: sources.clone(),
+        },
+        Command::MakeMoveVec(type_tag, elements) => CommandPreview::MakeMoveVec {
+            type_argument: type_tag
+                .as_ref()
+                .map(|type_tag| type_tag.to_canonical_string(true)),
+            elements: elements.clone(),
+        },
+        Command::Publish(modules, dependencies) => CommandPreview::Publish {
+            modules: modules.len(),
+            dependencies: dependencies.clone(),
+        },
+        Command::Upgrade(modules, dependencies, package, _) => CommandPreview::Upgrade {
+            modules: modules.len(),
+            dependencies: dependencies.clone(),
+            package: *package,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sui_types::base_types::SequenceNumber;
+    use crate::sui_types::digests::ObjectDigest;
+    use crate::utils::ptb::TxBuilder;
+
+    #[test]
+    fn preview_transfers() {
+        let registry = CurrencyRegistry::new([
+            serde_json::from_value::<CurrencyInfo>(serde_json::json!({
+                "currencyType": 2,
+                "currency": "OCT",
+                "name": "OneChain",
+                "exchangeRate": "1",
+                "displayDecimals": 4,
+                "calculateDecimals": 9,
+                "createTime": 0,
+                "updateTime": 0,
+                "symbol": "OCT",
+                "coinType": "0x0000000000000000000000000000000000000000000000000000000000000002::oct::OCT",
+            }))
+            .unwrap(),
+        ]);
+        assert!(registry.get(GAS_COIN_TYPE).is_some());
+
+        let sender = SuiAddress::random_for_testing_only();
+        let (alice, bob) = (
+            SuiAddress::random_for_testing_only(),
+            SuiAddress::random_for_testing_only(),
+        );
+        let usdt = (
+            ObjectID::random(),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::random(),
+        );
+        let mut tx = TxBuilder::new();
+        tx.inner()
+            .pay_sui(vec![alice], vec![1_500_000_000])
+            .unwrap();
+        tx.split_coin(usdt, vec![25], bob).unwrap();
+        tx.move_call("0x2", "coin", "zero", &[GAS_COIN_TYPE], vec![])
+            .unwrap();
+        let tx_bytes = tx.finish_data(sender, 1_000_000, 1000).unwrap();
+
+        let preview = TxPreviewer::new(registry)
+            .coin_type(usdt.0, "0x2::usdt::USDT")
+            .preview(&tx_bytes)
+            .unwrap();
+        assert_eq!(preview.sender, sender);
+        assert_eq!(preview.gas_owner, sender);
+        assert_eq!(preview.gas_budget.formatted.as_deref(), Some("0.001"));
+        assert_eq!(preview.move_calls.len(), 1);
+        assert!(preview.move_calls[0].ends_with("::coin::zero"));
+
+        assert_eq!(preview.transfers.len(), 2);
+        let paid = preview.transfers[0].amount.as_ref().unwrap();
+        assert_eq!(preview.transfers[0].recipient, Some(alice));
+        assert_eq!(paid.formatted.as_deref(), Some("1.5"));
+        assert_eq!(paid.symbol.as_deref(), Some("OCT"));
+        let paid = preview.transfers[1].amount.as_ref().unwrap();
+        assert_eq!(preview.transfers[1].recipient, Some(bob));
+        assert_eq!(paid.raw, "25");
+        assert_eq!(paid.symbol, None);
+
+        let json = serde_json::to_value(&preview).unwrap();
+        assert_eq!(json["commands"][0]["kind"], "splitCoins");
+        assert!(TxPreviewer::default().preview("invalid").is_err());
+    }
+}
@@ -5,22 +5,404 @@
 //!
 //! # Main functions
 //!
+//! * Generate ephemeral key pairs, `jwt_randomness` and the nonce for `get_token`
+//! * Build the `get_zk_proofs` request from the token response
 //! * Generate ZK Login authentication materials
+//! * Inspect JWT claims and derive zkLogin addresses with [`ZkLoginIdentity`]
 //! * Use ZK Login for transaction signature
+//! * Sign personal messages, e.g. login challenges or order confirmations
+//! * Verify ZK Login transaction and personal message signatures offline
+//! * Hold ephemeral key pairs without leaking them through `Debug`
 //!
+//! # Example
+//!
+//! ```no_run
+//! use onechain_wallet_rust_sdk::rpc::DIDApi;
+//! use onechain_wallet_rust_sdk::sui_types::crypto::SignatureScheme;
+//! use onechain_wallet_rust_sdk::types::did::{AuthorizeTokenProfileReq, Provider, LoginType};
+//! use onechain_wallet_rust_sdk::utils::zk_login::{
+//!     EphemeralKeyPair, generate_jwt_randomness, zk_proofs_req,
+//! };
+//!
+//! async fn example<T: DIDApi>(client: &T, code: String, max_epoch: u64) -> anyhow::Result<()> {
+//!     let keypair = EphemeralKeyPair::generate(SignatureScheme::ED25519)?;
+//!     let jwt_randomness = generate_jwt_randomness();
+//!     let req = AuthorizeTokenProfileReq {
+//!         code,
+//!         nonce: keypair.nonce(max_epoch, &jwt_randomness)?,
+//!         provider: Provider::Huione,
+//!         login_type: LoginType::Sms,
+//!     };
+//!     let token = client.get_token(req).await?.into_result()?;
+//!     let req = zk_proofs_req(&keypair, max_epoch, &jwt_randomness, &token);
+//!     let reader = client.get_zk_proofs(req).await?.into_result()?;
+//!     Ok(())
+//! }
+//! ```
+use crate::fastcrypto::ed25519::Ed25519KeyPair;
 use crate::fastcrypto::encoding::{Base64, Encoding};
-use crate::fastcrypto::jwt_utils::parse_and_validate_jwt;
-use crate::fastcrypto::traits::EncodeDecodeBase64;
-use crate::fastcrypto_zkp::bn254::utils::gen_address_seed;
-use crate::fastcrypto_zkp::bn254::zk_login::{ZkLoginInputs, ZkLoginInputsReader};
-use crate::shared_crypto::intent::{Intent, IntentMessage};
-use crate::sui_types::crypto::{PublicKey, Signature, SuiKeyPair, ZkLoginPublicIdentifier};
+use crate::fastcrypto::hash::{Blake2b256, HashFunction};
+use crate::fastcrypto::secp256k1::Secp256k1KeyPair;
+use crate::fastcrypto::secp256r1::Secp256r1KeyPair;
+use crate::fastcrypto::traits::{EncodeDecodeBase64, KeyPair};
+use crate::fastcrypto_zkp::bn254::utils::{gen_address_seed, get_nonce};
+use crate::fastcrypto_zkp::bn254::zk_login::{JWK, JwkId, ZkLoginInputs, ZkLoginInputsReader};
+use crate::fastcrypto_zkp::bn254::zk_login_api::{ZkLoginEnv, verify_zk_login};
+use crate::fastcrypto_zkp::zk_login_utils::Bn254FrElement;
+use crate::shared_crypto::intent::{Intent, IntentMessage, PersonalMessage};
+use crate::sui_types::base_types::SuiAddress;
+use crate::sui_types::crypto::{
+    PublicKey, Signature, SignatureScheme, SuiKeyPair, SuiSignature, ZkLoginPublicIdentifier,
+};
 use crate::sui_types::signature::GenericSignature;
-use crate::sui_types::transaction::TransactionData;
+use crate::sui_types::transaction::{TransactionData, TransactionDataAPI};
 use crate::sui_types::zk_login_authenticator::ZkLoginAuthenticator;
+use crate::types::common::ExtraFields;
+use crate::types::did::{AuthorizeTokenProfileResp, ZkProofsReq};
+use crate::types::timestamp::Timestamp;
+use crate::utils::chain::MAX_EPOCH_DELTA;
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use num_bigint::BigUint;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// JWT claim identifying the user, used for the address seed
+pub const KEY_CLAIM_NAME: &'static str = "sub";
+
+/// Ephemeral key pair used to sign transactions in a zkLogin session
+///
+/// `Debug` only prints the public key. The fastcrypto private keys wrapped by [`SuiKeyPair`]
+/// zeroize themselves on drop, and byte copies returned by [`EphemeralKeyPair::to_bytes`]
+/// are zeroized when dropped as well.
+pub struct EphemeralKeyPair(SuiKeyPair);
+
+impl EphemeralKeyPair {
+    pub fn new(skp: SuiKeyPair) -> Self {
+        Self(skp)
+    }
+
+    /// Generates a key pair with the thread-local cryptographically secure random number generator
+    ///
+    /// # Error
+    ///
+    /// Returns an error for schemes other than Ed25519, Secp256k1 and Secp256r1
+    pub fn generate(scheme: SignatureScheme) -> anyhow::Result<Self> {
+        let mut rng = rand::thread_rng();
+        let skp = match scheme {
+            SignatureScheme::ED25519 => SuiKeyPair::Ed25519(Ed25519KeyPair::generate(&mut rng)),
+            SignatureScheme::Secp256k1 => {
+                SuiKeyPair::Secp256k1(Secp256k1KeyPair::generate(&mut rng))
+            }
+            SignatureScheme::Secp256r1 => {
+                SuiKeyPair::Secp256r1(Secp256r1KeyPair::generate(&mut rng))
+            }
+            scheme => anyhow::bail!("unsupported ephemeral key scheme: {:?}", scheme),
+        };
+        Ok(Self(skp))
+    }
+
+    /// Returns the wrapped key pair, e.g. for [`zklogin_sign_tx_bytes`]
+    pub fn keypair(&self) -> &SuiKeyPair {
+        &self.0
+    }
+
+    /// Returns the ephemeral public key
+    pub fn public(&self) -> PublicKey {
+        self.0.public()
+    }
+
+    /// Returns the flag-prefixed private key bytes, zeroized on drop
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        Zeroizing::new(self.0.to_bytes())
+    }
+
+    /// Returns the flag-prefixed public key bytes
+    pub fn public_bytes(&self) -> Vec<u8> {
+        let pk = self.public();
+        let mut bytes = Vec::with_capacity(pk.as_ref().len() + 1);
+        bytes.push(pk.flag());
+        bytes.extend_from_slice(pk.as_ref());
+        bytes
+    }
+
+    /// Returns `extended_ephemeral_public_key` of [`ZkProofsReq`], the flag-prefixed public key
+    /// as a big-endian decimal integer
+    pub fn extended_public_key(&self) -> String {
+        BigUint::from_bytes_be(&self.public_bytes()).to_string()
+    }
+
+    /// Computes the nonce of [`AuthorizeTokenProfileReq`](crate::types::did::AuthorizeTokenProfileReq)
+    ///
+    /// # Parameters
+    ///
+    /// * `max_epoch` - Last epoch in which the ephemeral key may sign
+    /// * `jwt_randomness` - Randomness from [`generate_jwt_randomness`], also sent in [`ZkProofsReq`]
+    pub fn nonce(&self, max_epoch: u64, jwt_randomness: &str) -> anyhow::Result<String> {
+        Ok(get_nonce(&self.public_bytes(), max_epoch, jwt_randomness)?)
+    }
+}
+
+impl From<SuiKeyPair> for EphemeralKeyPair {
+    fn from(skp: SuiKeyPair) -> Self {
+        Self(skp)
+    }
+}
+
+impl Debug for EphemeralKeyPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EphemeralKeyPair")
+            .field("public", &self.public().encode_base64())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Generates `jwt_randomness` from the operating system's random number generator
+///
+/// 128 random bits encoded as a decimal integer, which always fits the BN254 scalar field.
+pub fn generate_jwt_randomness() -> String {
+    let mut bytes = Zeroizing::new([0u8; 16]);
+    OsRng.fill_bytes(bytes.as_mut());
+    BigUint::from_bytes_be(bytes.as_ref()).to_string()
+}
+
+/// Builds the request of [`DIDApi::get_zk_proofs`](crate::rpc::DIDApi::get_zk_proofs)
+///
+/// # Parameters
+///
+/// * `keypair` - Ephemeral key pair committed to in the nonce
+/// * `max_epoch` - Max epoch committed to in the nonce
+/// * `jwt_randomness` - Randomness committed to in the nonce
+/// * `token` - Response of [`DIDApi::get_token`](crate::rpc::DIDApi::get_token)
+pub fn zk_proofs_req(
+    keypair: &EphemeralKeyPair,
+    max_epoch: u64,
+    jwt_randomness: &str,
+    token: &AuthorizeTokenProfileResp,
+) -> ZkProofsReq {
+    ZkProofsReq {
+        max_epoch: max_epoch as i64,
+        jwt_randomness: jwt_randomness.into(),
+        extended_ephemeral_public_key: keypair.extended_public_key(),
+        jwt: token.jwt_token.clone(),
+        salt: token.salt.clone(),
+        key_claim_name: KEY_CLAIM_NAME.to_string(),
+    }
+}
+
+/// Claims of a JWT issued for zkLogin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtClaims {
+    /// Issuer
+    pub iss: String,
+    /// Audience, a token listing several audiences is rejected
+    #[serde(deserialize_with = "de_audience")]
+    pub aud: String,
+    /// Subject
+    pub sub: String,
+    /// Nonce committing to the ephemeral public key
+    pub nonce: Option<String>,
+    /// Expiration time, in seconds
+    pub exp: Option<i64>,
+    /// Issuance time, in seconds
+    pub iat: Option<i64>,
+    /// Other claims
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+impl JwtClaims {
+    /// Decodes the payload of a JWT without verifying its signature
+    pub fn decode(jwt: &str) -> anyhow::Result<Self> {
+        let mut parts = jwt.split('.');
+        let (Some(_), Some(payload), Some(_), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            anyhow::bail!("JWT must consist of three parts");
+        };
+        let payload = BASE64_URL_SAFE_NO_PAD.decode(payload.trim_end_matches('='))?;
+        Ok(serde_json::from_slice(&payload)?)
+    }
+
+    /// Returns a string claim by name
+    pub fn claim(&self, name: &str) -> Option<&str> {
+        match name {
+            "iss" => Some(&self.iss),
+            "aud" => Some(&self.aud),
+            "sub" => Some(&self.sub),
+            "nonce" => self.nonce.as_deref(),
+            _ => self.extra.get(name).and_then(|value| value.as_str()),
+        }
+    }
+
+    /// Expiration time, `None` if the token has no `exp` claim
+    pub fn expires_at(&self) -> Option<Timestamp> {
+        self.exp.map(Timestamp::from_secs)
+    }
+}
+
+fn de_audience<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        One(String),
+        Many(Vec<String>),
+    }
+    match Audience::deserialize(deserializer)? {
+        Audience::One(aud) => Ok(aud),
+        // zkLogin commits to a single audience, picking one of several would be arbitrary
+        Audience::Many(aud) => match <[String; 1]>::try_from(aud) {
+            Ok([aud]) => Ok(aud),
+            Err(aud) => Err(serde::de::Error::custom(format!(
+                "aud claim must hold one audience, got {}",
+                aud.len()
+            ))),
+        },
+    }
+}
+
+/// zkLogin identity derived from a JWT and the user salt
+///
+/// The address depends on the issuer, the audience, the key claim and the salt. The JWT
+/// signature is not verified here, it is checked by the prover and by the chain.
+#[derive(Debug, Clone)]
+pub struct ZkLoginIdentity {
+    claims: JwtClaims,
+    key_claim_name: String,
+    address_seed: String,
+}
+
+impl ZkLoginIdentity {
+    /// Derives the identity keyed by the `sub` claim
+    pub fn from_jwt(jwt: &str, salt: &str) -> anyhow::Result<Self> {
+        Self::with_key_claim(jwt, salt, KEY_CLAIM_NAME)
+    }
+
+    /// Derives the identity keyed by another string claim, e.g. `email`
+    pub fn with_key_claim(jwt: &str, salt: &str, key_claim_name: &str) -> anyhow::Result<Self> {
+        let claims = JwtClaims::decode(jwt)?;
+        let key_claim_value = claims
+            .claim(key_claim_name)
+            .ok_or_else(|| anyhow::anyhow!("JWT has no string claim {}", key_claim_name))?;
+        let address_seed = gen_address_seed(salt, key_claim_name, key_claim_value, &claims.aud)?;
+        Ok(Self {
+            key_claim_name: key_claim_name.to_string(),
+            address_seed,
+            claims,
+        })
+    }
+
+    pub fn claims(&self) -> &JwtClaims {
+        &self.claims
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.claims.iss
+    }
+
+    pub fn audience(&self) -> &str {
+        &self.claims.aud
+    }
+
+    pub fn key_claim_name(&self) -> &str {
+        &self.key_claim_name
+    }
+
+    /// Address seed as a decimal integer
+    pub fn address_seed(&self) -> &str {
+        &self.address_seed
+    }
+
+    /// zkLogin public identifier, from which [`ZkLoginIdentity::legacy_address`] is derived
+    pub fn public_key(&self) -> anyhow::Result<PublicKey> {
+        Ok(PublicKey::ZkLogin(ZkLoginPublicIdentifier::new(
+            self.issuer(),
+            &self.seed_element()?,
+        )?))
+    }
+
+    /// Address derived from the unpadded address seed, used by current wallets
+    ///
+    /// `sui_types` only derives it from proven inputs with `SuiAddress::try_from_unpadded`, so
+    /// the same hash is computed here to know the address before the proof is requested.
+    /// [`ZkLoginIdentity::inputs`] checks both derivations agree.
+    pub fn address(&self) -> anyhow::Result<SuiAddress> {
+        let seed = self.seed_element()?;
+        let iss = self.issuer().as_bytes();
+        let mut hasher = Blake2b256::default();
+        hasher.update([SignatureScheme::ZkLoginAuthenticator.flag()]);
+        hasher.update([iss.len() as u8]);
+        hasher.update(iss);
+        hasher.update(seed.unpadded());
+        Ok(SuiAddress::from_bytes(hasher.finalize().digest)?)
+    }
+
+    /// Address derived from the 32-byte padded address seed, used by early wallets
+    ///
+    /// Matches `SuiAddress::try_from_padded` for the proven inputs.
+    pub fn legacy_address(&self) -> anyhow::Result<SuiAddress> {
+        Ok(SuiAddress::from(&self.public_key()?))
+    }
+
+    /// Whether the JWT expired at `now`, tokens without `exp` never expire
+    pub fn is_expired_at(&self, now: Timestamp) -> bool {
+        self.claims.expires_at().is_some_and(|exp| exp <= now)
+    }
+
+    /// Fails if the JWT has expired
+    pub fn check_expiry(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !self.is_expired_at(Timestamp::now()),
+            "JWT expired at {}",
+            self.claims.expires_at().unwrap_or_default()
+        );
+        Ok(())
+    }
+
+    /// Fails unless the JWT carries the nonce computed for the ephemeral key, see
+    /// [`EphemeralKeyPair::nonce`]
+    pub fn check_nonce(&self, expected: &str) -> anyhow::Result<()> {
+        match self.claims.nonce.as_deref() {
+            Some(nonce) if nonce == expected => Ok(()),
+            Some(nonce) => anyhow::bail!("JWT nonce {} does not match {}", nonce, expected),
+            None => anyhow::bail!("JWT has no nonce claim"),
+        }
+    }
+
+    /// Combines the identity with the proof returned by `get_zk_proofs`
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the address of the proven inputs differs from
+    /// [`ZkLoginIdentity::address`], e.g. because the proof was issued for another issuer
+    pub fn inputs(&self, reader: ZkLoginInputsReader) -> anyhow::Result<ZkLoginInputs> {
+        let inputs = ZkLoginInputs::from_reader(reader, &self.address_seed)?;
+        let proven = SuiAddress::try_from_unpadded(&inputs)?;
+        let expected = self.address()?;
+        anyhow::ensure!(
+            proven == expected,
+            "proof for issuer {} yields address {}, expected {}",
+            inputs.get_iss(),
+            proven,
+            expected
+        );
+        Ok(inputs)
+    }
+
+    fn seed_element(&self) -> anyhow::Result<Bn254FrElement> {
+        Bn254FrElement::from_str(&self.address_seed)
+            .map_err(|e| anyhow::anyhow!("invalid address seed: {}", e))
+    }
+}
 
 /// Generate the materials required for ZK Login authentication
 ///
+/// The address seed is keyed by the `sub` claim, use [`ZkLoginIdentity::with_key_claim`] for
+/// other claims.
+///
 /// # Parameters
 ///
 /// * `jwt_token` - JWT token string
@@ -41,10 +423,8 @@ pub fn zklogin_material(
     salt: &str,
     reader: ZkLoginInputsReader,
 ) -> anyhow::Result<(PublicKey, ZkLoginInputs)> {
-    // Calculate address_seed and address
-    let (sub, aud, _) = parse_and_validate_jwt(jwt_token)?;
-    let address_seed = gen_address_seed(salt, "sub", &sub, &aud)?;
-    let zk_login_inputs = ZkLoginInputs::from_reader(reader, &address_seed.to_string())?;
+    let identity = ZkLoginIdentity::from_jwt(jwt_token, salt)?;
+    let zk_login_inputs = identity.inputs(reader)?;
     let pk = PublicKey::ZkLogin(ZkLoginPublicIdentifier::new(
         zk_login_inputs.get_iss(),
         zk_login_inputs.get_address_seed(),
@@ -66,3 +446,485 @@ pub fn zklogin_sign_tx_bytes<T: AsRef<str>>(
     let final_sig: GenericSignature = zk_login_authenticator.into();
     Ok(final_sig.encode_base64())
 }
+
+/// Signs a personal message with the ephemeral key of a zkLogin session
+///
+/// The message is signed with the personal message intent, so the signature cannot be replayed
+/// as a transaction signature.
+///
+/// # Return value
+///
+/// Returns the base64 encoded `GenericSignature`, verifiable with
+/// [`zklogin_verify_personal_message`]
+pub fn zklogin_sign_personal_message(
+    max_epoch: u64,
+    skp: &SuiKeyPair,
+    zk_login_inputs: ZkLoginInputs,
+    message: &[u8],
+) -> anyhow::Result<String> {
+    let intent_msg = IntentMessage::new(
+        Intent::personal_message(),
+        PersonalMessage {
+            message: message.to_vec(),
+        },
+    );
+    let s = Signature::new_secure(&intent_msg, skp);
+    let zk_login_authenticator = ZkLoginAuthenticator::new(zk_login_inputs, max_epoch, s);
+    let final_sig: GenericSignature = zk_login_authenticator.into();
+    Ok(final_sig.encode_base64())
+}
+
+/// Inputs for verifying the Groth16 proof of a zkLogin signature
+#[derive(Debug, Clone)]
+pub struct ProofVerifyParams {
+    /// Selects the verifying key, [`ZkLoginEnv::Prod`] for proofs of the production prover
+    pub env: ZkLoginEnv,
+    /// JSON Web Keys of the OIDC providers, by issuer and key ID
+    pub jwks: im::HashMap<JwkId, JWK>,
+}
+
+impl ProofVerifyParams {
+    /// Creates parameters for the verifying key of `env` without any JWK, add the keys of the
+    /// accepted providers with [`ProofVerifyParams::with_jwk`]
+    pub fn new(env: ZkLoginEnv) -> Self {
+        Self {
+            env,
+            jwks: im::HashMap::new(),
+        }
+    }
+
+    /// Adds the key `kid` of issuer `iss`
+    pub fn with_jwk(mut self, iss: &str, kid: &str, jwk: JWK) -> Self {
+        self.jwks
+            .insert(JwkId::new(iss.to_string(), kid.to_string()), jwk);
+        self
+    }
+}
+
+/// Verifies a signature returned by [`zklogin_sign_tx_bytes`] without contacting the chain
+///
+/// Checks that the signature is a zkLogin signature, that the ephemeral signature covers the
+/// transaction, that the zkLogin address is the transaction sender and that `max_epoch` has
+/// not passed. The Groth16 proof is only verified if `proof` is given.
+///
+/// # Parameters
+///
+/// * `signature` - Base64 encoded `GenericSignature`
+/// * `tx_bytes` - Base64 encoded BCS `TransactionData`
+/// * `current_epoch` - Epoch the transaction will be executed in
+/// * `proof` - Verifying key and JWKs, `None` to skip proof verification
+///
+/// # Return value
+///
+/// Returns the zkLogin address of the signer
+pub fn zklogin_verify_tx_signature<T: AsRef<str>>(
+    signature: &str,
+    tx_bytes: T,
+    current_epoch: u64,
+    proof: Option<&ProofVerifyParams>,
+) -> anyhow::Result<SuiAddress> {
+    let tx_data: TransactionData = bcs::from_bytes(&Base64::decode(tx_bytes.as_ref())?)?;
+    let sender = tx_data.sender();
+    let intent_msg = IntentMessage::new(Intent::sui_transaction(), tx_data);
+    verify_zklogin_signature(signature, &intent_msg, sender, current_epoch, proof)?;
+    Ok(sender)
+}
+
+/// Verifies a signature returned by [`zklogin_sign_personal_message`] without contacting the chain
+///
+/// Performs the same checks as [`zklogin_verify_tx_signature`], with `address` taking the
+/// place of the transaction sender.
+///
+/// # Parameters
+///
+/// * `signature` - Base64 encoded `GenericSignature`
+/// * `message` - Signed message
+/// * `address` - Expected zkLogin address of the signer
+/// * `current_epoch` - Epoch at which the signature must still be valid
+/// * `proof` - Verifying key and JWKs, `None` to skip proof verification
+pub fn zklogin_verify_personal_message(
+    signature: &str,
+    message: &[u8],
+    address: SuiAddress,
+    current_epoch: u64,
+    proof: Option<&ProofVerifyParams>,
+) -> anyhow::Result<()> {
+    let intent_msg = IntentMessage::new(
+        Intent::personal_message(),
+        PersonalMessage {
+            message: message.to_vec(),
+        },
+    );
+    verify_zklogin_signature(signature, &intent_msg, address, current_epoch, proof)
+}
+
+/// Verifies a zkLogin signature over an intent message signed by `author`
+fn verify_zklogin_signature<T: Serialize>(
+    signature: &str,
+    intent_msg: &IntentMessage<T>,
+    author: SuiAddress,
+    current_epoch: u64,
+    proof: Option<&ProofVerifyParams>,
+) -> anyhow::Result<()> {
+    let GenericSignature::ZkLoginAuthenticator(authenticator) =
+        GenericSignature::decode_base64(signature)
+            .map_err(|e| anyhow::anyhow!("invalid signature: {}", e))?
+    else {
+        anyhow::bail!("not a zkLogin signature");
+    };
+
+    let max_epoch = authenticator.get_max_epoch();
+    anyhow::ensure!(
+        current_epoch <= max_epoch,
+        "zkLogin signature expired at epoch {}, current epoch is {}",
+        max_epoch,
+        current_epoch
+    );
+    anyhow::ensure!(
+        max_epoch - current_epoch <= MAX_EPOCH_DELTA,
+        "max epoch {} is more than {} epochs after current epoch {}",
+        max_epoch,
+        MAX_EPOCH_DELTA,
+        current_epoch
+    );
+
+    let inputs = &authenticator.inputs;
+    let unpadded = SuiAddress::try_from_unpadded(inputs)?;
+    let padded = SuiAddress::try_from_padded(inputs)?;
+    anyhow::ensure!(
+        author == unpadded || author == padded,
+        "zkLogin address {} does not match signer {}",
+        unpadded,
+        author
+    );
+
+    // The ephemeral key is not the signer, verify against its own address
+    let user_signature = &authenticator.user_signature;
+    let scheme = user_signature.scheme();
+    let ephemeral_pk = PublicKey::try_from_bytes(scheme, user_signature.public_key_bytes())
+        .map_err(|e| anyhow::anyhow!("invalid ephemeral public key: {}", e))?;
+    user_signature.verify_secure(intent_msg, SuiAddress::from(&ephemeral_pk), scheme)?;
+
+    if let Some(proof) = proof {
+        let mut eph_pk_bytes = vec![scheme.flag()];
+        eph_pk_bytes.extend_from_slice(user_signature.public_key_bytes());
+        verify_zk_login(inputs, max_epoch, &eph_pk_bytes, &proof.jwks, &proof.env)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fastcrypto::encoding::Hex;
+    use crate::fastcrypto_zkp::bn254::zk_login::{OIDCProvider, parse_jwks};
+    use crate::sui_types::utils::get_zklogin_inputs;
+    use crate::sui_types::zk_login_util::DEFAULT_JWK_BYTES;
+    use crate::utils::ptb::TxBuilder;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// Max epoch the Sui test vector proof was generated for
+    const FIXTURE_MAX_EPOCH: u64 = 10;
+
+    /// Ephemeral key and inputs of the Sui test vectors, proven for [`ZkLoginEnv::Test`]
+    fn fixture() -> (SuiKeyPair, ZkLoginInputs, SuiAddress) {
+        let keypair =
+            SuiKeyPair::Ed25519(Ed25519KeyPair::generate(&mut StdRng::from_seed([0; 32])));
+        let inputs = get_zklogin_inputs();
+        let address = SuiAddress::try_from_unpadded(&inputs).unwrap();
+        (keypair, inputs, address)
+    }
+
+    fn tx_bytes(sender: SuiAddress, gas_budget: u64) -> String {
+        TxBuilder::new()
+            .finish_data(sender, gas_budget, 1000)
+            .unwrap()
+    }
+
+    fn proof_params() -> ProofVerifyParams {
+        let jwks = parse_jwks(DEFAULT_JWK_BYTES, &OIDCProvider::Twitch, true).unwrap();
+        ProofVerifyParams {
+            env: ZkLoginEnv::Test,
+            jwks: jwks.into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn ephemeral_key_material() {
+        for scheme in [
+            SignatureScheme::ED25519,
+            SignatureScheme::Secp256k1,
+            SignatureScheme::Secp256r1,
+        ] {
+            let keypair = EphemeralKeyPair::generate(scheme).unwrap();
+            let bytes = keypair.public_bytes();
+            assert_eq!(bytes[0], scheme.flag());
+            let extended = keypair.extended_public_key();
+            assert_eq!(
+                BigUint::parse_bytes(extended.as_bytes(), 10).unwrap(),
+                BigUint::from_bytes_be(&bytes)
+            );
+            let randomness = generate_jwt_randomness();
+            assert!(keypair.nonce(10, &randomness).is_ok());
+        }
+        assert!(EphemeralKeyPair::generate(SignatureScheme::MultiSig).is_err());
+        assert_ne!(generate_jwt_randomness(), generate_jwt_randomness());
+    }
+
+    #[test]
+    fn ephemeral_key_debug_redacted() {
+        let keypair = EphemeralKeyPair::generate(SignatureScheme::ED25519).unwrap();
+        let debug = format!("{:?}", keypair);
+        assert!(debug.contains(&keypair.public().encode_base64()));
+        assert!(!debug.contains(&Base64::encode(&*keypair.to_bytes())));
+        assert!(!debug.contains(&Hex::encode(&*keypair.to_bytes())));
+    }
+
+    #[test]
+    fn identity_claims() {
+        let payload = serde_json::json!({
+            "iss": "https://accounts.example.com",
+            "aud": ["wallet"],
+            "sub": "123456",
+            "email": "user@example.com",
+            "nonce": "abc",
+            "exp": 1700000000,
+        });
+        let jwt = format!(
+            "e30.{}.c2ln",
+            BASE64_URL_SAFE_NO_PAD.encode(payload.to_string())
+        );
+        let identity = ZkLoginIdentity::from_jwt(&jwt, "42").unwrap();
+        assert_eq!(identity.audience(), "wallet");
+        assert!(identity.check_nonce("abc").is_ok());
+        assert!(identity.check_nonce("abd").is_err());
+        assert!(identity.is_expired_at(Timestamp::from_secs(1700000000)));
+        assert!(!identity.is_expired_at(Timestamp::from_secs(1699999999)));
+
+        let by_email = ZkLoginIdentity::with_key_claim(&jwt, "42", "email").unwrap();
+        assert_ne!(by_email.address_seed(), identity.address_seed());
+        assert!(ZkLoginIdentity::with_key_claim(&jwt, "42", "phone").is_err());
+
+        for aud in [
+            serde_json::json!([]),
+            serde_json::json!(["wallet", "other"]),
+        ] {
+            let mut payload = payload.clone();
+            payload["aud"] = aud;
+            let jwt = format!(
+                "e30.{}.c2ln",
+                BASE64_URL_SAFE_NO_PAD.encode(payload.to_string())
+            );
+            let err = ZkLoginIdentity::from_jwt(&jwt, "42").unwrap_err();
+            assert!(err.to_string().contains("one audience"));
+        }
+    }
+
+    #[test]
+    fn address_matches_sui_fixtures() {
+        let (_, fixture_inputs, fixture_address) = fixture();
+        let reader: ZkLoginInputsReader =
+            serde_json::from_value(serde_json::to_value(&fixture_inputs).unwrap()).unwrap();
+        let claims: JwtClaims = serde_json::from_value(serde_json::json!({
+            "iss": fixture_inputs.get_iss(),
+            "aud": "wallet",
+            "sub": "123456",
+        }))
+        .unwrap();
+        // The fixture seed and a seed with leading zero bytes, where padding changes the address
+        for address_seed in [
+            fixture_inputs.get_address_seed().to_string(),
+            "12345".to_string(),
+        ] {
+            let identity = ZkLoginIdentity {
+                claims: claims.clone(),
+                key_claim_name: KEY_CLAIM_NAME.to_string(),
+                address_seed,
+            };
+            let inputs = identity.inputs(reader.clone()).unwrap();
+            assert_eq!(
+                identity.address().unwrap(),
+                SuiAddress::try_from_unpadded(&inputs).unwrap()
+            );
+            assert_eq!(
+                identity.legacy_address().unwrap(),
+                SuiAddress::try_from_padded(&inputs).unwrap()
+            );
+        }
+
+        let identity = ZkLoginIdentity {
+            claims: claims.clone(),
+            key_claim_name: KEY_CLAIM_NAME.to_string(),
+            address_seed: fixture_inputs.get_address_seed().to_string(),
+        };
+        assert_eq!(identity.address().unwrap(), fixture_address);
+        let seed_12345 = ZkLoginIdentity {
+            address_seed: "12345".to_string(),
+            ..identity
+        };
+        assert_ne!(
+            seed_12345.address().unwrap(),
+            seed_12345.legacy_address().unwrap()
+        );
+
+        let other_issuer = ZkLoginIdentity {
+            claims: serde_json::from_value(serde_json::json!({
+                "iss": "https://accounts.example.com",
+                "aud": "wallet",
+                "sub": "123456",
+            }))
+            .unwrap(),
+            ..seed_12345
+        };
+        let err = other_issuer.inputs(reader).unwrap_err();
+        assert!(err.to_string().contains("expected"));
+    }
+
+    #[test]
+    fn personal_message_requires_zklogin() {
+        let keypair = EphemeralKeyPair::generate(SignatureScheme::ED25519).unwrap();
+        let message = b"login challenge".to_vec();
+        let intent_msg = IntentMessage::new(
+            Intent::personal_message(),
+            PersonalMessage {
+                message: message.clone(),
+            },
+        );
+        let signature: GenericSignature =
+            Signature::new_secure(&intent_msg, keypair.keypair()).into();
+        let address = SuiAddress::from(&keypair.public());
+        let err =
+            zklogin_verify_personal_message(&signature.encode_base64(), &message, address, 0, None)
+                .unwrap_err();
+        assert!(err.to_string().contains("not a zkLogin signature"));
+    }
+
+    #[test]
+    fn tx_signature_round_trip() {
+        let (keypair, inputs, address) = fixture();
+        let tx = tx_bytes(address, 1_000_000);
+        let signature = zklogin_sign_tx_bytes(FIXTURE_MAX_EPOCH, &keypair, inputs, &tx).unwrap();
+
+        let signer = zklogin_verify_tx_signature(&signature, &tx, FIXTURE_MAX_EPOCH, None).unwrap();
+        assert_eq!(signer, address);
+        let signer =
+            zklogin_verify_tx_signature(&signature, &tx, FIXTURE_MAX_EPOCH, Some(&proof_params()))
+                .unwrap();
+        assert_eq!(signer, address);
+    }
+
+    #[test]
+    fn tx_signature_rejected() {
+        let (keypair, inputs, address) = fixture();
+        let tx = tx_bytes(address, 1_000_000);
+        let signature =
+            zklogin_sign_tx_bytes(FIXTURE_MAX_EPOCH, &keypair, inputs.clone(), &tx).unwrap();
+
+        // Wrong sender
+        let other = tx_bytes(SuiAddress::random_for_testing_only(), 1_000_000);
+        let other_signature =
+            zklogin_sign_tx_bytes(FIXTURE_MAX_EPOCH, &keypair, inputs, &other).unwrap();
+        let err = zklogin_verify_tx_signature(&other_signature, &other, FIXTURE_MAX_EPOCH, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("does not match signer"));
+
+        // Expired max epoch
+        let err =
+            zklogin_verify_tx_signature(&signature, &tx, FIXTURE_MAX_EPOCH + 1, None).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        // Tampered transaction bytes
+        let tampered = tx_bytes(address, 2_000_000);
+        assert!(
+            zklogin_verify_tx_signature(&signature, &tampered, FIXTURE_MAX_EPOCH, None).is_err()
+        );
+
+        // Wrong JWK for the key ID of the proof
+        let params = ProofVerifyParams {
+            jwks: proof_params()
+                .jwks
+                .into_iter()
+                .map(|(id, mut jwk)| {
+                    jwk.n = BASE64_URL_SAFE_NO_PAD.encode([0xab; 256]);
+                    (id, jwk)
+                })
+                .collect(),
+            ..proof_params()
+        };
+        assert!(
+            zklogin_verify_tx_signature(&signature, &tx, FIXTURE_MAX_EPOCH, Some(&params)).is_err()
+        );
+        // No JWK at all
+        let params = ProofVerifyParams::new(ZkLoginEnv::Test);
+        assert!(
+            zklogin_verify_tx_signature(&signature, &tx, FIXTURE_MAX_EPOCH, Some(&params)).is_err()
+        );
+    }
+
+    #[test]
+    fn personal_message_round_trip() {
+        let (keypair, inputs, address) = fixture();
+        let message = b"login challenge";
+        let signature =
+            zklogin_sign_personal_message(FIXTURE_MAX_EPOCH, &keypair, inputs, message).unwrap();
+
+        zklogin_verify_personal_message(&signature, message, address, FIXTURE_MAX_EPOCH, None)
+            .unwrap();
+        zklogin_verify_personal_message(
+            &signature,
+            message,
+            address,
+            FIXTURE_MAX_EPOCH,
+            Some(&proof_params()),
+        )
+        .unwrap();
+        assert!(
+            zklogin_verify_personal_message(
+                &signature,
+                b"other challenge",
+                address,
+                FIXTURE_MAX_EPOCH,
+                None
+            )
+            .is_err()
+        );
+        let err = zklogin_verify_personal_message(
+            &signature,
+            message,
+            SuiAddress::random_for_testing_only(),
+            FIXTURE_MAX_EPOCH,
+            None,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not match signer"));
+    }
+
+    #[test]
+    fn signatures_bound_to_intent() {
+        let (keypair, inputs, address) = fixture();
+        let tx = tx_bytes(address, 1_000_000);
+        let tx_raw = Base64::decode(&tx).unwrap();
+
+        // A transaction signature is not a personal message signature over the same bytes
+        let tx_signature =
+            zklogin_sign_tx_bytes(FIXTURE_MAX_EPOCH, &keypair, inputs.clone(), &tx).unwrap();
+        assert!(
+            zklogin_verify_personal_message(
+                &tx_signature,
+                &tx_raw,
+                address,
+                FIXTURE_MAX_EPOCH,
+                None
+            )
+            .is_err()
+        );
+
+        // A personal message signature over transaction bytes does not authorize the transaction
+        let message_signature =
+            zklogin_sign_personal_message(FIXTURE_MAX_EPOCH, &keypair, inputs, &tx_raw).unwrap();
+        assert!(
+            zklogin_verify_tx_signature(&message_signature, &tx, FIXTURE_MAX_EPOCH, None).is_err()
+        );
+    }
+}
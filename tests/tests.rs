@@ -1,26 +1,24 @@
-use num_bigint::BigUint;
 use onechain_wallet_rust_sdk::client::OneChainWalletService;
-use onechain_wallet_rust_sdk::fastcrypto::ed25519::Ed25519KeyPair;
-use onechain_wallet_rust_sdk::fastcrypto::encoding::{Base64, Encoding};
-use onechain_wallet_rust_sdk::fastcrypto::traits::KeyPair;
-use onechain_wallet_rust_sdk::fastcrypto_zkp::bn254::utils::get_nonce;
 use onechain_wallet_rust_sdk::fastcrypto_zkp::bn254::zk_login::ZkLoginInputs;
 use onechain_wallet_rust_sdk::rpc::{DIDApi, TransferApi};
 use onechain_wallet_rust_sdk::sui_types::base_types::SuiAddress;
-use onechain_wallet_rust_sdk::sui_types::crypto::SuiKeyPair;
-use onechain_wallet_rust_sdk::sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
-use onechain_wallet_rust_sdk::sui_types::transaction::TransactionData;
-use onechain_wallet_rust_sdk::types::common::ACCESS_TOKEN;
+use onechain_wallet_rust_sdk::sui_types::crypto::{SignatureScheme, SuiKeyPair};
+use onechain_wallet_rust_sdk::types::common::{ACCESS_TOKEN, Secret};
 use onechain_wallet_rust_sdk::types::did::{
-    AuthorizeTokenProfileReq, SmsAuthenticateReq, SmsCodeSendReq, ZkProofsReq,
+    AuthorizeTokenProfileReq, LoginType, Provider, SmsAuthenticateReq, SmsCodeSendReq,
 };
 use onechain_wallet_rust_sdk::types::transfer::{
-    BuildSponsorTxReq, ProxyPayTxReq, TransferOrderReq, TransferOrderTxReq,
+    ProxyPayTxReq, TransferOrderReq, TransferOrderTxReq,
+};
+use onechain_wallet_rust_sdk::utils::chain::{JsonRpcChainInfo, select_max_epoch};
+use onechain_wallet_rust_sdk::utils::ptb::{SponsorTxForm, TxBuilder};
+use onechain_wallet_rust_sdk::utils::zk_login::{
+    EphemeralKeyPair, generate_jwt_randomness, zk_proofs_req, zklogin_material,
+    zklogin_sign_tx_bytes,
 };
-use onechain_wallet_rust_sdk::utils::zk_login::{zklogin_material, zklogin_sign_tx_bytes};
-use rand::{random, thread_rng};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::time::Duration;
 use sui_sdk::SuiClientBuilder;
 use sui_sdk::rpc_types::SuiObjectDataOptions;
 use sui_sdk::types::base_types::ObjectID;
@@ -33,7 +31,7 @@ pub struct WalletInfo {
     pub merchant_id: String,
     pub keypair: SuiKeyPair,
     pub zk_login_inputs: ZkLoginInputs,
-    pub access_token: String,
+    pub access_token: Secret<String>,
     pub token_id: String,
     pub max_epoch: u64,
 }
@@ -49,15 +47,13 @@ async fn init_service() -> anyhow::Result<UserWallet> {
     let mut service =
         OneChainWalletService::new("https://api.example.com", PRI_KEY, "merchant_id", None)?;
 
-    let sui_client = SuiClientBuilder::default()
-        .build("https://rpc-devnet.onelabs.cc:443")
-        .await?;
+    let chain = JsonRpcChainInfo::new("https://rpc-devnet.onelabs.cc:443", None)?;
 
     // 1.
     let req = SmsCodeSendReq {
         mobile: "123123123".to_string(),
         mobile_prefix: "855".to_string(),
-        provider: "huione".to_string(),
+        provider: Provider::Huione,
     };
 
     let resp = service.send_code(req).await?.get_data()?;
@@ -66,50 +62,43 @@ async fn init_service() -> anyhow::Result<UserWallet> {
     let req = SmsAuthenticateReq {
         mobile_prefix: "855".to_string(),
         mobile: "123123123".to_string(),
-        provider: "huione".to_string(),
-        sms_code: "000000".to_string(),
+        provider: Provider::Huione,
+        sms_code: "000000".into(),
         code: resp,
     };
 
     let rsp = service.sms(req).await?.get_data()?;
 
-    let summary = sui_client
-        .governance_api()
-        .get_latest_sui_system_state()
-        .await?;
-
-    let max_epoch = 30 + summary.epoch;
-    let skp = SuiKeyPair::Ed25519(Ed25519KeyPair::generate(&mut thread_rng()));
-    let jwt_randomness = random::<i64>().to_string();
-    let eph_pk_bytes = skp.to_bytes();
-    let nonce = get_nonce(&eph_pk_bytes, max_epoch, &jwt_randomness)?;
+    let max_epoch = select_max_epoch(&chain, Duration::from_secs(7 * 24 * 3600)).await?;
+    let keypair = EphemeralKeyPair::generate(SignatureScheme::ED25519)?;
+    let jwt_randomness = generate_jwt_randomness();
+    let nonce = keypair.nonce(max_epoch, &jwt_randomness)?;
 
     let req = AuthorizeTokenProfileReq {
-        provider: "huione".to_string(),
+        provider: Provider::Huione,
         code: rsp.code,
-        login_type: "sms".to_string(),
+        login_type: LoginType::Sms,
         nonce,
     };
 
     // 3.
     let rsp = service.get_token(req).await?.get_data()?;
-    let kp_bigint = BigUint::from_bytes_be(&eph_pk_bytes).to_string();
-    service.set_header(ACCESS_TOKEN.to_string(), rsp.access_token.clone());
+    service.set_header(
+        ACCESS_TOKEN.to_string(),
+        rsp.access_token.expose_secret().clone(),
+    );
     let access_token = rsp.access_token;
     let token_id = rsp.access_token_profile.jti;
 
-    let req = ZkProofsReq {
-        max_epoch: max_epoch as i64,
-        jwt_randomness: jwt_randomness.clone(),
-        extended_ephemeral_public_key: kp_bigint.clone(),
-        jwt: rsp.jwt_token.clone(),
-        salt: rsp.salt.clone(),
-        key_claim_name: "sub".to_string(),
-    };
+    let req = zk_proofs_req(&keypair, max_epoch, &jwt_randomness, &rsp);
     // 4. get zk prover
     let reader = service.get_zk_proofs(req).await?.get_data()?;
 
-    let (pk, zk_login_inputs) = zklogin_material(&rsp.jwt_token, &rsp.salt, reader)?;
+    let (pk, zk_login_inputs) = zklogin_material(
+        rsp.jwt_token.expose_secret(),
+        rsp.salt.expose_secret(),
+        reader,
+    )?;
 
     let address = SuiAddress::from(&pk);
 
@@ -119,7 +108,7 @@ async fn init_service() -> anyhow::Result<UserWallet> {
             address,
             did: rsp.did,
             merchant_id: "1000000".to_string(),
-            keypair: skp,
+            keypair: keypair.keypair().copy(),
             zk_login_inputs,
             access_token,
             token_id,
@@ -134,9 +123,11 @@ async fn transfer_test() {
     let wallet = init_service().await.unwrap();
 
     let req = TransferOrderReq {
-        from_address: wallet.info.address.to_string(),
-        to_address: "0x643e921c885d47795e3753803193f37ff70c44a412be9c046de51263f39ba0b5"
-            .to_string(),
+        from_address: wallet.info.address,
+        to_address: SuiAddress::from_str(
+            "0x643e921c885d47795e3753803193f37ff70c44a412be9c046de51263f39ba0b5",
+        )
+        .unwrap(),
         coin_type: "OCT".to_string(),
         amount: 0.001.to_string(),
         remark: None,
@@ -203,26 +194,14 @@ async fn sponsor_test() {
         .data
         .unwrap();
 
-    let pt = {
-        let mut builder = ProgrammableTransactionBuilder::new();
-        builder
-            .transfer_object(wallet.info.address.clone(), object.object_ref())
-            .unwrap();
-        builder.finish()
-    };
-    let data =
-        TransactionData::new_programmable(wallet.info.address.clone(), vec![], pt, 100000000, 1000);
-
-    let data_bytes = bcs::to_bytes(&data).unwrap();
-
-    let tx_base64 = Base64::encode(&data_bytes);
-
-    let req = BuildSponsorTxReq {
-        address: wallet.info.address.to_string(),
-        raw_transaction: tx_base64,
-        only_transaction_kind: false,
-        gas_budget: Some(0.01.to_string()),
+    let mut tx = TxBuilder::new();
+    tx.transfer_object(wallet.info.address, object.object_ref())
+        .unwrap();
+    let form = SponsorTxForm::Data {
+        gas_budget: 100_000_000,
+        gas_price: 1000,
     };
+    let req = tx.sponsor_req(wallet.info.address, form).unwrap();
 
     let resp = wallet
         .service